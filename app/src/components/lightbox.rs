@@ -0,0 +1,213 @@
+use leptos::prelude::*;
+use photogrid::{PhotoId, PhotoLayoutData};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::PhotoSelectionContext;
+
+/// the largest version of a photo and where to find it again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightboxPhoto {
+    pub id: PhotoId,
+    pub src: String,
+    pub original: String,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl LightboxPhoto {
+    pub fn new(photo: &PhotoLayoutData) -> Option<Self> {
+        let largest = photo.largest()?;
+        Some(Self {
+            id: photo.id()?,
+            src: largest.url.to_string(),
+            original: largest.original()?.to_string(),
+            width: largest.dimensions.width,
+            height: largest.dimensions.height,
+        })
+    }
+}
+
+/// the order a grid reads its photos in on the viewports matching `media`,
+/// every viewport when there is none
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightboxOrder {
+    pub media: Option<String>,
+    /// positions in the photos of the lightbox
+    pub photos: Vec<usize>,
+}
+
+/// the page showing only the photo. `query` names the photos it steps
+/// through, those of the grid it was opened from
+pub fn photo_permalink(id: PhotoId, query: &str) -> String {
+    match query {
+        "" => format!("/photo/{id}"),
+        query => format!("/photo/{id}?{query}"),
+    }
+}
+
+#[cfg(all(feature = "hydrate", target_arch = "wasm32"))]
+fn matches_media(query: &str) -> bool {
+    web_sys::window()
+        .and_then(|window| window.match_media(query).ok().flatten())
+        .is_some_and(|list| list.matches())
+}
+
+/// nothing is shown before hydrating so no viewport is matched
+#[cfg(not(all(feature = "hydrate", target_arch = "wasm32")))]
+fn matches_media(_: &str) -> bool {
+    false
+}
+
+/// the photo before or after `idx` in the order of the grid on the viewport
+fn neighbour(orders: &[LightboxOrder], idx: usize, forward: bool) -> Option<usize> {
+    let order = orders
+        .iter()
+        .find(|order| order.media.as_deref().is_none_or(matches_media))
+        .or(orders.first())?;
+    let position = order.photos.iter().position(|x| *x == idx)?;
+    match forward {
+        true => order.photos.get(position + 1).copied(),
+        false => position
+            .checked_sub(1)
+            .and_then(|position| order.photos.get(position))
+            .copied(),
+    }
+}
+
+/// shows the photo clicked inside `children` over the page. photos are found
+/// by the `data-photo-id` attribute of the clicked element or its ancestors.
+/// the arrow keys step through `photos` in the order the grid shown on the
+/// viewport reads them, escape closes it and `s` toggles the shown photo in
+/// the selection when there is one. permalinks keep the `query` naming the
+/// photos of the grid
+#[island]
+pub fn Lightbox(
+    photos: Vec<LightboxPhoto>,
+    orders: Vec<LightboxOrder>,
+    query: String,
+    children: Children,
+) -> impl IntoView {
+    let (open, set_open) = signal(None::<usize>);
+    let photos = StoredValue::new(photos);
+    let orders = StoredValue::new(orders);
+    let query = StoredValue::new(query);
+    let selection = use_context::<PhotoSelectionContext>();
+
+    let next_to =
+        move |idx: usize, forward: bool| orders.with_value(|o| neighbour(o, idx, forward));
+    let step = move |forward: bool| {
+        if let Some(idx) = open.get_untracked().and_then(|idx| next_to(idx, forward)) {
+            set_open.set(Some(idx));
+        }
+    };
+
+    #[cfg(all(feature = "hydrate", target_arch = "wasm32"))]
+    if let Some(window) = web_sys::window() {
+        let listener = gloo::events::EventListener::new(&window, "keydown", move |ev| {
+            let Some(ev) = ev.dyn_ref::<web_sys::KeyboardEvent>() else {
+                return;
+            };
+            if open.get_untracked().is_none() {
+                return;
+            }
+            match ev.key().as_str() {
+                "Escape" => set_open.set(None),
+                "ArrowLeft" => step(false),
+                "ArrowRight" => step(true),
+                "s" => match (selection, open.get_untracked()) {
+                    (Some(selection), Some(idx)) => {
+                        selection.toggle_selected(photos.with_value(|p| p[idx].id))
+                    }
+                    _ => return,
+                },
+                _ => return,
+            }
+            ev.prevent_default();
+        });
+        listener.forget();
+    }
+
+    let on_click = move |ev: web_sys::MouseEvent| {
+        // leave opening the permalink in a new tab to the browser
+        if ev.button() != 0 || ev.ctrl_key() || ev.meta_key() || ev.shift_key() || ev.alt_key() {
+            return;
+        }
+        let idx = ev
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .and_then(|el| el.closest("[data-photo-id]").ok().flatten())
+            .and_then(|el| el.get_attribute("data-photo-id"))
+            .and_then(|id| id.parse::<PhotoId>().ok())
+            .and_then(|id| photos.with_value(|p| p.iter().position(|photo| photo.id == id)));
+        if let Some(idx) = idx {
+            ev.prevent_default();
+            set_open.set(Some(idx));
+        }
+    };
+
+    let overlay = move || {
+        open.get().map(|idx| {
+            let photo = photos.with_value(|p| p[idx].clone());
+            let permalink = query.with_value(|query| photo_permalink(photo.id, query));
+            view! {
+                <div
+                    class="fixed inset-0 z-50 flex flex-col items-center justify-center gap-2 bg-black/90 p-4 font-mono text-white"
+                    role="dialog"
+                    aria-modal="true"
+                    on:click=move |_| set_open.set(None)
+                >
+                    <img
+                        class="min-h-0 max-h-full max-w-full object-contain"
+                        src=photo.src
+                        width=photo.width
+                        height=photo.height
+                        on:click=|ev| ev.stop_propagation()
+                    />
+                    <div class="flex gap-4" on:click=|ev| ev.stop_propagation()>
+                        <button disabled=next_to(idx, false).is_none() on:click=move |_| step(false)>
+                            "prev"
+                        </button>
+                        <a href=permalink>"permalink"</a>
+                        <a href=photo.original>"original"</a>
+                        <button on:click=move |_| set_open.set(None)>"close"</button>
+                        <button disabled=next_to(idx, true).is_none() on:click=move |_| step(true)>
+                            "next"
+                        </button>
+                    </div>
+                </div>
+            }
+        })
+    };
+
+    view! {
+        <div class="contents" on:click=on_click>
+            {children()}
+        </div>
+        {overlay}
+    }
+}
+
+/// keyboard navigation between the permalink pages of photos. the arrow keys
+/// go to `prev` and `next` and escape goes to `close`
+#[island]
+pub fn PhotoKeys(prev: Option<String>, next: Option<String>, close: String) -> impl IntoView {
+    #[cfg(all(feature = "hydrate", target_arch = "wasm32"))]
+    if let Some(window) = web_sys::window() {
+        let listener = gloo::events::EventListener::new(&window, "keydown", move |ev| {
+            let Some(ev) = ev.dyn_ref::<web_sys::KeyboardEvent>() else {
+                return;
+            };
+            let href = match ev.key().as_str() {
+                "Escape" => Some(&close),
+                "ArrowLeft" => prev.as_ref(),
+                "ArrowRight" => next.as_ref(),
+                _ => None,
+            };
+            if let Some(href) = href {
+                let _ = web_sys::window().map(|window| window.location().set_href(href));
+            }
+        });
+        listener.forget();
+    }
+}
@@ -1,5 +1,7 @@
 mod canvas_header;
+mod lightbox;
 mod photo_grid;
 
 pub use canvas_header::*;
+pub use lightbox::*;
 pub use photo_grid::*;
@@ -1,99 +1,290 @@
-use grid::Size;
+use std::sync::Arc;
+
+use grid::{pack::FirstFit, Size};
 use leptos::prelude::*;
-use photogrid::{PhotoLayoutData, ResponsivePhotoGrid, SrcSet};
-use url::Url;
+use photogrid::{
+    join_ids, parse_ids, Breakpoint, FillConfig, PhotoId, PhotoLayoutData, ResponsivePhotoGrid,
+    SrcSet,
+};
+use wasm_bindgen::prelude::*;
+
+use super::{photo_permalink, Lightbox, LightboxOrder, LightboxPhoto};
 
+/// the local storage key the selection is kept under between visits
+const SELECTION_KEY: &str = "photo-selection";
+
+/// keeps the photos selected inside `children` in local storage. shift
+/// clicking a photo toggles it
 #[island]
 pub fn SelectionProvider(children: Children) -> impl IntoView {
     let (selected, set_selected) = signal(Vec::new());
-    provide_context(PhotoSelectionContext {
+    let context = PhotoSelectionContext {
         set_selected,
         selected,
+    };
+    provide_context(context);
+
+    // the stored selection is only read once hydrated so the first render
+    // matches the server. nothing is written back before it was read
+    let (loaded, set_loaded) = signal(false);
+    #[cfg(all(feature = "hydrate", target_arch = "wasm32"))]
+    request_animation_frame(move || {
+        let stored = web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(SELECTION_KEY).ok().flatten());
+        if let Some(stored) = stored {
+            set_selected.set(parse_ids(&stored));
+        }
+        set_loaded.set(true);
     });
-    children()
+    Effect::new(move |_| {
+        let ids = join_ids(&selected.read());
+        if !loaded.get() {
+            return;
+        }
+        let storage = web_sys::window().and_then(|window| window.local_storage().ok().flatten());
+        if let Some(storage) = storage {
+            let _ = storage.set_item(SELECTION_KEY, &ids);
+        }
+    });
+
+    let on_click = move |ev: web_sys::MouseEvent| {
+        if !ev.shift_key() {
+            return;
+        }
+        let id = ev
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .and_then(|el| el.closest("[data-photo-id]").ok().flatten())
+            .and_then(|el| el.get_attribute("data-photo-id"))
+            .and_then(|id| id.parse().ok());
+        if let Some(id) = id {
+            ev.prevent_default();
+            context.toggle_selected(id);
+        }
+    };
+
+    view! {
+        <div class="contents" on:click=on_click>
+            {children()}
+        </div>
+    }
 }
 
 #[derive(Clone, Copy)]
-struct PhotoSelectionContext {
-    set_selected: WriteSignal<Vec<Url>>,
-    selected: ReadSignal<Vec<Url>>,
+pub(crate) struct PhotoSelectionContext {
+    set_selected: WriteSignal<Vec<PhotoId>>,
+    selected: ReadSignal<Vec<PhotoId>>,
 }
 
 impl PhotoSelectionContext {
-    fn toggle_selected(&self, url: Url) {
-        self.set_selected
-            .update(|val| match self.is_selected(&url) {
-                Some(idx) => {
-                    val.remove(idx);
-                }
-                None => {
-                    val.push(url.clone());
-                }
-            });
+    pub(crate) fn toggle_selected(&self, id: PhotoId) {
+        self.set_selected.update(|val| match self.is_selected(id) {
+            Some(idx) => {
+                val.remove(idx);
+            }
+            None => {
+                val.push(id);
+            }
+        });
     }
 
-    fn is_selected(&self, url: &Url) -> Option<usize> {
+    fn is_selected(&self, id: PhotoId) -> Option<usize> {
         self.selected
             .read_untracked()
             .iter()
             .enumerate()
-            .find_map(|(idx, x)| (x == url).then_some(idx))
+            .find_map(|(idx, x)| (*x == id).then_some(idx))
     }
 }
 
-fn use_is_selected(url: Url) -> Signal<Option<usize>> {
+fn use_is_selected(id: PhotoId) -> Signal<Option<usize>> {
     let PhotoSelectionContext { selected, .. } = expect_context();
     Signal::derive(move || {
         selected
             .read()
             .iter()
             .enumerate()
-            .find_map(|(idx, e)| (e == &url).then_some(idx))
+            .find_map(|(idx, e)| (*e == id).then_some(idx))
     })
 }
 
+/// toggles a photo in the selection, showing its place in the selection order
+#[island]
+fn SelectBadge(id: PhotoId) -> impl IntoView {
+    let context = expect_context::<PhotoSelectionContext>();
+    let selected = use_is_selected(id);
+
+    view! {
+        <button
+            class="absolute right-2 top-2 flex h-6 w-6 items-center justify-center rounded-full border border-white font-mono text-xs text-white shadow"
+            class=("bg-black", move || selected.get().is_some())
+            aria-pressed=move || selected.get().is_some().to_string()
+            title="select"
+            on:click=move |_| context.toggle_selected(id)
+        >
+            {move || selected.get().map(|idx| idx + 1)}
+        </button>
+    }
+}
+
+/// the size of the selection with links to share and export it
+#[island]
+fn SelectionBar() -> impl IntoView {
+    let PhotoSelectionContext {
+        selected,
+        set_selected,
+    } = expect_context();
+    let ids = move || join_ids(&selected.read());
+
+    view! {
+        <Show when=move || !selected.read().is_empty()>
+            <div class="sticky bottom-0 z-40 flex gap-4 bg-white/90 p-2 font-mono text-sm">
+                <span>{move || format!("{} selected", selected.read().len())}</span>
+                <a href=move || format!("/search?ids={}", ids())>"share"</a>
+                <a href=move || format!("/export?ids={}", ids()) download="photos.txt">
+                    "export originals"
+                </a>
+                <button on:click=move |_| set_selected.set(Vec::new())>"clear"</button>
+            </div>
+        </Show>
+    }
+}
+
+/// the width of the variant loaded by browsers which ignore the srcset
+const FALLBACK_SRC_WIDTH: usize = 1024;
+
+/// the attributes of the `<img>` of a photo spanning a number of columns on
+/// each breakpoint. the dimensions reserve its space before it loads
+#[derive(Debug, PartialEq)]
+struct TileImage {
+    srcset: String,
+    sizes: String,
+    src: Option<String>,
+    width: Option<usize>,
+    height: Option<usize>,
+}
+
+impl TileImage {
+    fn new(photo: &PhotoLayoutData, spans: &[(Breakpoint, usize)]) -> Self {
+        let dimensions = photo.dimensions();
+        Self {
+            srcset: srcsets(photo.srcs.iter()),
+            sizes: crate::style::image_sizes(spans),
+            src: photo
+                .src_at_least(FALLBACK_SRC_WIDTH)
+                .map(|src| src.url.to_string()),
+            width: dimensions.map(|d| d.width),
+            height: dimensions.map(|d| d.height),
+        }
+    }
+}
+
+/// lay out the photos for every breakpoint
+pub fn layout_photos(data: Vec<PhotoLayoutData>) -> ResponsivePhotoGrid<PhotoLayoutData> {
+    ResponsivePhotoGrid::from_layout_data(data, &FirstFit).fill_holes(FillConfig::default())
+}
+
+/// the photos the lightbox can show and the order each breakpoint reads them in
+fn lightbox_photos(
+    data: &ResponsivePhotoGrid<PhotoLayoutData>,
+    breakpoints: &[Breakpoint],
+) -> (Vec<LightboxPhoto>, Vec<LightboxOrder>) {
+    let mut photos = Vec::new();
+    let positions: Vec<Option<usize>> = data
+        .data()
+        .iter()
+        .map(|photo| {
+            photos.push(LightboxPhoto::new(photo)?);
+            Some(photos.len() - 1)
+        })
+        .collect();
+    let orders = data
+        .layouts()
+        .iter()
+        .zip(breakpoints)
+        .map(|(grid, breakpoint)| LightboxOrder {
+            media: breakpoint.media_query(),
+            photos: grid
+                .reading_order()
+                .filter_map(|idx| positions.get(*idx).copied().flatten())
+                .collect(),
+        })
+        .collect();
+    (photos, orders)
+}
+
+/// every photo rendered once, placed by the breakpoint matching the viewport.
+/// photos link to permalinks keeping the `query` naming the photos shown
 #[component]
-pub fn PhotoGridComponent(data: Vec<PhotoLayoutData>) -> impl IntoView {
+pub fn PhotoGridComponent(
+    data: Arc<ResponsivePhotoGrid<PhotoLayoutData>>,
+    query: String,
+) -> impl IntoView {
     use crate::style::*;
 
-    let data = ResponsivePhotoGrid::from_layout_data(data);
-
-    let _ = "col-span-1 col-span-2 col-span-3 col-span-4 col-span-5 col-span-6 col-span-7 col-span-8 col-span-9 col-span-10 col-span-11 col-span-12";
-    let _ = "row-span-1 row-span-2 row-span-3 row-span-4 row-span-5 row-span-6 row-span-7 row-span-8 row-span-9 row-span-10 row-span-11 row-span-12";
-    let _ = "col-start-1 col-start-2 col-start-3 col-start-4 col-start-5 col-start-6 col-start-7 col-start-8 col-start-9 col-start-10 col-start-11 col-start-12";
-    let _ = "row-start-1 row-start-2 row-start-3 row-start-4 row-start-5 row-start-6 row-start-7 row-start-8 row-start-9 row-start-10 row-start-11 row-start-12";
-
-    data.grids()
-        .map(|grid| {
-            let class = grid.style(GridOuterClass);
-            view! {
-                <div class=class>
-                    {grid
-                        .grid
-                        .into_iter()
-                        .map(move |c| {
-                            let content = c.content();
-                            let class = format!(
-                                "p-1 flex items-center justify-center {}",
-                                c.style(GridElemClass),
-                            );
-                            let style = c.style(GridElemStyle);
-                            let srcset = srcsets(content.srcs.iter());
-                            view! {
-                                <SinglePhoto class=class style=style>
-                                    <img
-                                        class="object-contain max-h-full max-w-full w-full"
-                                        srcset=srcset
-                                        loading="lazy"
-                                    />
-                                </SinglePhoto>
-                            }
-                        })
-                        .collect_view()}
-                </div>
+    let breakpoints: Vec<Breakpoint> = data
+        .layouts()
+        .iter()
+        .enumerate()
+        .map(|(idx, grid)| {
+            data.breakpoints()
+                .get(idx)
+                .copied()
+                .unwrap_or(Breakpoint::new(0, None, grid.width))
+        })
+        .collect();
+    let css = grid_breakpoints_css(&breakpoints);
+    let (lightbox, orders) = lightbox_photos(&data, &breakpoints);
+    let tiles = data
+        .contents()
+        .filter_map(|(content, placements)| {
+            // left off every breakpoint
+            if placements.iter().all(Option::is_none) {
+                return None;
             }
+            let id = content.id();
+            let class = "relative p-1 flex items-center justify-center".to_string();
+            let style = placements.style(GridElemStyle);
+            let spans: Vec<_> = breakpoints
+                .iter()
+                .zip(&placements)
+                .filter_map(|(breakpoint, c)| Some((*breakpoint, c.as_ref()?.size.width)))
+                .collect();
+            let image = TileImage::new(content, &spans);
+            let href = id.map(|id| photo_permalink(id, &query));
+            Some(view! {
+                <SinglePhoto class=class style=style>
+                    <a
+                        class="contents"
+                        href=href
+                        data-photo-id=id.map(|id| id.to_string())
+                    >
+                        <img
+                            class="object-contain max-h-full max-w-full w-full"
+                            srcset=image.srcset
+                            sizes=image.sizes
+                            src=image.src
+                            width=image.width
+                            height=image.height
+                            loading="lazy"
+                        />
+                    </a>
+                    {id.map(|id| view! { <SelectBadge id /> })}
+                </SinglePhoto>
+            })
         })
-        .collect_view()
+        .collect_view();
+
+    view! {
+        <style>{css}</style>
+        <SelectionProvider>
+            <Lightbox photos=lightbox orders query>
+                <div class=GRID_OUTER_CLASS>{tiles}</div>
+            </Lightbox>
+            <SelectionBar />
+        </SelectionProvider>
+    }
 }
 
 #[component]
@@ -106,29 +297,84 @@ fn SinglePhoto(class: String, style: String, children: Children) -> impl IntoVie
 }
 
 fn srcsets<'a>(s: impl Iterator<Item = &'a SrcSet>) -> String {
-    s.fold(String::new(), |mut acc, cur| {
-        acc.push_str(cur.url.as_str());
-        acc.push(' ');
-        acc.push_str(cur.dimensions.width().to_string().as_str());
-        acc.push_str("w,");
-        acc
-    })
+    s.map(|cur| format!("{} {}w", cur.url, cur.dimensions.width()))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
-fn original(s: &Url) -> Url {
-    let mut out = s.clone();
-    let mut segments = s.path_segments().expect("This must have a base");
-
-    out.path_segments_mut()
-        .expect("this must have a base")
-        .clear()
-        .push("original")
-        .push(
-            segments
-                .next_back()
-                .expect("There is a file here")
-                .replace(".avif", ".jpg")
-                .as_str(),
+#[cfg(test)]
+mod tests {
+    use photogrid::DEFAULT_BREAKPOINTS;
+
+    use super::*;
+
+    fn photos() -> Vec<PhotoLayoutData> {
+        serde_json::from_str(include_str!("../../../data.json")).unwrap()
+    }
+
+    fn photo() -> PhotoLayoutData {
+        photos().into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn it_should_size_full_width_photos() {
+        let [mobile, tablet, ..] = DEFAULT_BREAKPOINTS;
+        let image = TileImage::new(&photo(), &[(mobile, 3), (tablet, 4)]);
+        assert_eq!(image.sizes, "(max-width: 639.98px) 100vw, 100vw");
+        assert_eq!(
+            image.src.as_deref(),
+            Some("https://cdn.seanaye.ca/resized/1024x4294967295/_AYE7507.avif")
         );
-    out
+        assert_eq!((image.width, image.height), (Some(2048), Some(1367)));
+    }
+
+    #[test]
+    fn it_should_size_partial_spans() {
+        let [mobile, _, small, _, wide] = DEFAULT_BREAKPOINTS;
+        let image = TileImage::new(&photo(), &[(mobile, 3), (small, 4), (wide, 3)]);
+        // 4 of 6 columns rounds up so the browser never picks a smaller src
+        assert_eq!(
+            image.sizes,
+            "(max-width: 639.98px) 100vw, (min-width: 768px) and (max-width: 1023.98px) 67vw, 25vw"
+        );
+    }
+
+    #[test]
+    fn it_should_leave_out_missing_dimensions() {
+        let mut photo = photo();
+        photo.srcs.clear();
+        let image = TileImage::new(&photo, &[]);
+        assert_eq!(
+            image,
+            TileImage {
+                srcset: String::new(),
+                sizes: "100vw".to_string(),
+                src: None,
+                width: None,
+                height: None,
+            }
+        );
+    }
+
+    #[test]
+    fn lightbox_should_follow_the_grid() {
+        let mut photos = photos();
+        photos.truncate(12);
+        let data = layout_photos(photos);
+        let (lightbox, orders) = lightbox_photos(&data, data.breakpoints());
+
+        assert_eq!(lightbox.len(), 12);
+        // every photo spans the mobile grid so it reads in the order laid out
+        assert_eq!(orders[0].photos, (0..12).collect::<Vec<_>>());
+        orders.iter().zip(data.layouts()).for_each(|(order, grid)| {
+            assert_eq!(order.photos.len(), grid.grid.len());
+            let origins: Vec<_> = order
+                .photos
+                .iter()
+                .filter_map(|idx| grid.grid.iter().find(|c| c.content() == idx))
+                .map(|c| (c.origin.y, c.origin.x))
+                .collect();
+            assert!(origins.is_sorted(), "{origins:?}");
+        });
+    }
 }
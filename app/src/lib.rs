@@ -20,10 +20,13 @@ use components::*;
 pub mod error_template;
 use leptos_router::params::Params;
 use photo_search::SearchFilter;
-use photogrid::PhotoLayoutData;
+use photogrid::{
+    join_ids, parse_ids, LayoutCache, PhotoId, PhotoIndex, PhotoLayoutData, ResponsivePhotoGrid,
+};
 use pulldown_cmark::{html, Options, Parser};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
 mod style;
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
@@ -72,6 +75,7 @@ pub fn App() -> impl IntoView {
                     <Route path=StaticSegment("/blog") view=BlogPage />
                     <Route path=(StaticSegment("/blog"), ParamSegment("slug")) view=BlogPostPage />
                     <Route path=StaticSegment("/photo") view=PhotoPage />
+                    <Route path=(StaticSegment("/photo"), ParamSegment("id")) view=PhotoDetailPage />
                     <Route path=StaticSegment("/search") view=SearchPage />
                 </Routes>
                 <NavBar />
@@ -348,13 +352,55 @@ fn BlogPostPage() -> impl IntoView {
     }
 }
 
+/// the seed used when neither the query nor the clock provide one
+const DEFAULT_PHOTO_SEED: u64 = 0;
+
+/// the photos shown on the photo page
+const PHOTO_PAGE_FILTER: SearchFilter = SearchFilter {
+    before: Some(1727827200),
+    after: None,
+    rating: None,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+struct SeedParams(Option<u64>);
+
+impl Params for SeedParams {
+    fn from_map(map: &params::ParamsMap) -> Result<Self, params::ParamsError> {
+        Ok(Self(map.get_str("seed").and_then(|x| x.parse().ok())))
+    }
+}
+
+/// the seed of today's arrangement, the number of days since the unix epoch
+fn daily_seed() -> Option<u64> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some(now.as_secs() / (24 * 60 * 60))
+}
+
+/// the seed in the query, or today's when there is none
+fn use_photo_seed() -> u64 {
+    match use_query::<SeedParams>().get() {
+        Ok(SeedParams(Some(seed))) => seed,
+        _ => daily_seed().unwrap_or(DEFAULT_PHOTO_SEED),
+    }
+}
+
+/// the photos matching `f` in the order they are laid out, shuffled with
+/// `seed` when there is one
+fn arrange(data: &[PhotoLayoutData], f: &SearchFilter, seed: Option<u64>) -> Vec<PhotoLayoutData> {
+    let mut photos: Vec<PhotoLayoutData> = data.iter().filter(|x| f.matches(x)).cloned().collect();
+    if let Some(seed) = seed {
+        photos.shuffle(&mut StdRng::seed_from_u64(seed))
+    }
+    photos
+}
+
 #[component]
 fn PhotoPage() -> impl IntoView {
-    let photo_filter = SearchFilter {
-        before: Some(1727827200),
-        after: None,
-        rating: None,
-    };
+    let seed = use_photo_seed();
+    let source = PhotoSource::Page(seed);
 
     view! {
         <div class="contents">
@@ -362,59 +408,250 @@ fn PhotoPage() -> impl IntoView {
                 <div class="prose font-mono">
                     <Markdown content=include_str!("content/photo.md") />
                 </div>
-                <FilteredPhotoGrid f=photo_filter random=true />
+                <ArrangedPhotoGrid source=source.clone() />
+                <p class="font-mono text-sm">
+                    <a href=source.href()>{format!("arrangement {seed}")}</a>
+                </p>
             </LayoutContent>
         </div>
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
-struct SearchParams(SearchFilter);
+struct PhotoParams {
+    id: Option<PhotoId>,
+}
+
+impl Params for PhotoParams {
+    fn from_map(map: &params::ParamsMap) -> Result<Self, params::ParamsError> {
+        Ok(Self {
+            id: map.get_str("id").and_then(|x| x.parse().ok()),
+        })
+    }
+}
+
+/// the photos a grid shows, kept in the query of the permalinks of its
+/// photos so they step through the same photos
+#[derive(Debug, PartialEq, Clone)]
+enum PhotoSource {
+    /// the photo page shuffled with the seed
+    Page(u64),
+    /// the search page
+    Search(SearchParams),
+}
+
+impl PhotoSource {
+    /// the query of the permalinks of its photos
+    fn query(&self) -> String {
+        match self {
+            Self::Page(seed) => format!("seed={seed}"),
+            Self::Search(params) => match params.query() {
+                query if query.is_empty() => "from=search".to_string(),
+                query => format!("from=search&{query}"),
+            },
+        }
+    }
+
+    /// the page showing the grid
+    fn href(&self) -> String {
+        match self {
+            Self::Page(seed) => format!("/photo?seed={seed}"),
+            Self::Search(params) => match params.query() {
+                query if query.is_empty() => "/search".to_string(),
+                query => format!("/search?{query}"),
+            },
+        }
+    }
+}
+
+impl Params for PhotoSource {
+    fn from_map(map: &params::ParamsMap) -> Result<Self, params::ParamsError> {
+        match map.get_str("from") {
+            Some("search") => SearchParams::from_map(map).map(Self::Search),
+            _ => SeedParams::from_map(map).map(|SeedParams(seed)| {
+                Self::Page(seed.or_else(daily_seed).unwrap_or(DEFAULT_PHOTO_SEED))
+            }),
+        }
+    }
+}
+
+/// laid out photos and the position of each id in them
+pub struct Arrangement {
+    grid: Arc<ResponsivePhotoGrid<PhotoLayoutData>>,
+    index: PhotoIndex,
+}
+
+impl Arrangement {
+    fn new(photos: Vec<PhotoLayoutData>) -> Self {
+        let grid = layout_photos(photos);
+        let index = PhotoIndex::new(grid.data());
+        Self {
+            grid: Arc::new(grid),
+            index,
+        }
+    }
+}
+
+/// photo layouts keyed by the filter and the seed the photos were shuffled with
+pub type PhotoLayoutCache = LayoutCache<(SearchFilter, Option<u64>), Arrangement>;
+
+/// the photos of `source` laid out, from the layout cache when there is one.
+/// shared selections are laid out on every render
+fn use_arrangement(source: &PhotoSource) -> Arc<Arrangement> {
+    let data = use_context::<Arc<[PhotoLayoutData]>>().unwrap();
+    let (filter, seed) = match source {
+        PhotoSource::Page(seed) => (PHOTO_PAGE_FILTER, Some(*seed)),
+        PhotoSource::Search(SearchParams { ids: Some(ids), .. }) => {
+            let index = use_context::<Arc<PhotoIndex>>().unwrap();
+            let photos = ids
+                .iter()
+                .filter_map(|id| data.get(index.get(*id)?))
+                .cloned()
+                .collect();
+            return Arc::new(Arrangement::new(photos));
+        }
+        PhotoSource::Search(SearchParams { filter, .. }) => (*filter, None),
+    };
+
+    let build = || Arrangement::new(arrange(&data, &filter, seed));
+    match use_context::<Arc<PhotoLayoutCache>>() {
+        Some(cache) => cache.get_or_insert_with((filter, seed), build),
+        None => Arc::new(build()),
+    }
+}
+
+/// the permalink page of a single photo. prev and next step through the
+/// photos of the grid it was opened from, named by the query
+#[component]
+fn PhotoDetailPage() -> impl IntoView {
+    let data = use_context::<Arc<[PhotoLayoutData]>>().unwrap();
+    let index = use_context::<Arc<PhotoIndex>>().unwrap();
+    let source = use_query::<PhotoSource>()
+        .get()
+        .unwrap_or(PhotoSource::Page(DEFAULT_PHOTO_SEED));
+    let id = use_params::<PhotoParams>()
+        .get()
+        .ok()
+        .and_then(|params| params.id);
+    let arrangement = use_arrangement(&source);
+    let arranged = arrangement.grid.data();
+    let position = id.and_then(|id| arrangement.index.get(id));
+    let query = source.query();
+    let permalink = |position: usize| {
+        let id = arranged.get(position)?.id()?;
+        Some(photo_permalink(id, &query))
+    };
+    let close = source.href();
+
+    // photos left out of the grid are still shown, without prev and next
+    let photo = id
+        .and_then(|id| index.get(id))
+        .and_then(|idx| data.get(idx))
+        .and_then(LightboxPhoto::new);
+
+    let content = match photo {
+        Some(photo) => {
+            let prev = position
+                .and_then(|position| position.checked_sub(1))
+                .and_then(permalink);
+            let next = position.and_then(|position| permalink(position + 1));
+            view! {
+                <figure class="flex flex-col items-center gap-2 font-mono">
+                    <img
+                        class="max-h-[80lvh] max-w-full object-contain"
+                        src=photo.src
+                        width=photo.width
+                        height=photo.height
+                    />
+                    <figcaption class="flex gap-4">
+                        {prev.clone().map(|href| view! { <a href=href>"prev"</a> })}
+                        <a href=close.clone()>"back"</a>
+                        <a href=photo.original>"original"</a>
+                        {next.clone().map(|href| view! { <a href=href>"next"</a> })}
+                    </figcaption>
+                </figure>
+                <PhotoKeys prev next close />
+            }
+            .into_any()
+        }
+        None => {
+            let mut outside_errors = Errors::default();
+            outside_errors.insert_with_default_key(AppError::NotFound);
+            view! { <ErrorTemplate outside_errors /> }.into_any()
+        }
+    };
+
+    view! {
+        <div class="contents">
+            <LayoutContent>{content}</LayoutContent>
+        </div>
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+struct SearchParams {
+    filter: SearchFilter,
+    /// a shared selection, shown instead of the filtered photos
+    ids: Option<Vec<PhotoId>>,
+}
+
+impl SearchParams {
+    /// the query read back by [`SearchParams::from_map`]
+    fn query(&self) -> String {
+        if let Some(ids) = &self.ids {
+            return format!("ids={}", join_ids(ids));
+        }
+        let SearchFilter {
+            before,
+            after,
+            rating,
+        } = self.filter;
+        [
+            ("b", before.map(|x| x.to_string())),
+            ("a", after.map(|x| x.to_string())),
+            ("r", rating.map(|x| x.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some(format!("{key}={}", value?)))
+        .collect::<Vec<_>>()
+        .join("&")
+    }
+}
 
 impl Params for SearchParams {
     fn from_map(map: &params::ParamsMap) -> Result<Self, params::ParamsError> {
         let before = map.get_str("b").and_then(|x| x.parse().ok());
         let after = map.get_str("a").and_then(|x| x.parse().ok());
         let rating = map.get_str("r").and_then(|x| x.parse().ok());
-        Ok(Self(SearchFilter {
-            before,
-            after,
-            rating,
-        }))
+        Ok(Self {
+            filter: SearchFilter {
+                before,
+                after,
+                rating,
+            },
+            ids: map.get_str("ids").map(parse_ids),
+        })
     }
 }
 
 #[component]
 fn SearchPage() -> impl IntoView {
-    let params = use_query::<SearchParams>();
-    let f = match params.get() {
-        Ok(SearchParams(f)) => f,
-        Err(_) => SearchFilter::default(),
-    };
+    let params = use_query::<SearchParams>().get().unwrap_or_default();
     view! {
         <aside class="contents">
             <LayoutContent>
-                <FilteredPhotoGrid f=f random=false />
+                <ArrangedPhotoGrid source=PhotoSource::Search(params) />
             </LayoutContent>
         </aside>
     }
 }
 
+/// the photos of `source` in the order they were arranged
 #[component]
-fn FilteredPhotoGrid(f: SearchFilter, random: bool) -> impl IntoView {
-    let data = use_context::<Arc<[PhotoLayoutData]>>().unwrap();
-
-    let mut grid = data
-        .iter()
-        .filter(|x| f.matches(x))
-        .cloned()
-        .collect::<Vec<PhotoLayoutData>>();
-
-    if random {
-        grid.shuffle(&mut thread_rng())
-    }
+fn ArrangedPhotoGrid(source: PhotoSource) -> impl IntoView {
+    let arrangement = use_arrangement(&source);
 
-    view! { <PhotoGridComponent data=grid /> }.into_any()
+    view! { <PhotoGridComponent data=arrangement.grid.clone() query=source.query() /> }
 }
 
 #[island]
@@ -1,48 +1,128 @@
+use std::fmt::Write;
+
 use grid::GridContent;
-use photogrid::PhotoGrid;
+use photogrid::Breakpoint;
 
 pub trait CssStyle<T> {
     fn style(&self, marker: T) -> String;
 }
 
-pub struct GridElemClass;
-impl<T> CssStyle<GridElemClass> for GridContent<T> {
-    fn style(&self, _: GridElemClass) -> String {
-        let (size, origin) = self.grid_area();
-        let row_start = origin.y + 1;
-        let col_start = origin.x + 1;
-        let row_span = size.height;
-        let col_span = size.width;
-        format!(
-            "col-start-{col_start} row-start-{row_start} col-span-{col_span} row-span-{row_span}"
-        )
-    }
-}
+/// the class of the grid holding every photo, placed by [`grid_breakpoints_css`]
+pub const GRID_OUTER_CLASS: &str = "w-full photo-grid";
+
+/// the placement of a photo on each breakpoint as custom properties read by
+/// [`grid_breakpoints_css`]. photos missing from a breakpoint are hidden on it
 pub struct GridElemStyle;
-impl<T> CssStyle<GridElemStyle> for GridContent<T> {
+impl<T> CssStyle<GridElemStyle> for [Option<&GridContent<T>>] {
     fn style(&self, _: GridElemStyle) -> String {
-        let (size, _) = self.grid_area();
+        let mut style = String::new();
+        for (idx, content) in self.iter().enumerate() {
+            match content {
+                Some(content) => {
+                    let (size, origin) = content.grid_area();
+                    let row_start = origin.y + 1;
+                    let col_start = origin.x + 1;
+                    write!(
+                        style,
+                        "--area-{idx}: {row_start} / {col_start} / span {} / span {}; --aspect-{idx}: {}/{};",
+                        size.height, size.width, size.width, size.height
+                    )
+                }
+                None => write!(style, "--display-{idx}: none;"),
+            }
+            .expect("writing to a string can't fail");
+        }
+        style
+    }
+}
 
-        format!("aspect-ratio: {}/{};", size.width, size.height)
+/// the `sizes` of an image spanning a number of columns on each breakpoint,
+/// as a share of the viewport width. the last breakpoint is also the default
+pub fn image_sizes(spans: &[(Breakpoint, usize)]) -> String {
+    let vw = |(breakpoint, span): &(Breakpoint, usize)| (span * 100).div_ceil(breakpoint.columns);
+    let Some((last, rest)) = spans.split_last() else {
+        return "100vw".to_string();
+    };
+    rest.iter()
+        .filter_map(|span| Some(format!("{} {}vw", span.0.media_query()?, vw(span))))
+        .chain(std::iter::once(format!("{}vw", vw(last))))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// a stylesheet laying out the photos of the grid with the placement of the
+/// breakpoint matching the viewport
+pub fn grid_breakpoints_css(breakpoints: &[Breakpoint]) -> String {
+    let mut css = String::from(".photo-grid { display: grid; }\n");
+    for (idx, breakpoint) in breakpoints.iter().enumerate() {
+        let rules = format!(
+            ".photo-grid {{ grid-template-columns: repeat({}, minmax(0, 1fr)); }} \
+             .photo-grid > * {{ grid-area: var(--area-{idx}); aspect-ratio: var(--aspect-{idx}); display: var(--display-{idx}, flex); }}",
+            breakpoint.columns
+        );
+        match breakpoint.media_query() {
+            None => writeln!(css, "{rules}"),
+            Some(query) => writeln!(css, "@media {query} {{ {rules} }}"),
+        }
+        .expect("writing to a string can't fail");
     }
+    css
 }
 
-pub struct GridOuterClass;
-impl<T> CssStyle<GridOuterClass> for PhotoGrid<T> {
-    fn style(&self, _: GridOuterClass) -> String {
-        let width = self.width;
-
-        let display = match width {
-            12 => "hidden 2xl:grid grid-cols-12",
-            8 => "hidden lg:max-2xl:grid grid-cols-8",
-            6 => "hidden md:max-lg:grid grid-cols-6",
-            5 => "hidden md:max-lg:grid grid-cols-5",
-            4 => "hidden sm:max-md:grid grid-cols-4",
-            3 => "grid sm:hidden grid-cols-3",
-            1 => "grid sm:hidden grid-cols-1",
-            x => panic!("unmatched grid width {x}"),
+#[cfg(test)]
+mod tests {
+    use grid::{Coord, Dimension};
+
+    use super::*;
+
+    #[test]
+    fn it_should_place_photos_per_breakpoint() {
+        let placed = GridContent::new(
+            0,
+            Coord { x: 1, y: 2 },
+            Dimension {
+                width: 3,
+                height: 2,
+            },
+        );
+        let style = [Some(&placed), None, Some(&placed)].style(GridElemStyle);
+        assert_eq!(
+            style,
+            "--area-0: 3 / 2 / span 2 / span 3; --aspect-0: 3/2;\
+             --display-1: none;\
+             --area-2: 3 / 2 / span 2 / span 3; --aspect-2: 3/2;"
+        );
+    }
+
+    #[test]
+    fn it_should_write_a_rule_per_breakpoint() {
+        let rules = |idx: usize, columns: usize| {
+            format!(
+                ".photo-grid {{ grid-template-columns: repeat({columns}, minmax(0, 1fr)); }} \
+                 .photo-grid > * {{ grid-area: var(--area-{idx}); aspect-ratio: var(--aspect-{idx}); display: var(--display-{idx}, flex); }}"
+            )
         };
 
-        format!("w-full {display}")
+        let css = grid_breakpoints_css(&[
+            Breakpoint::new(0, Some(640), 3),
+            Breakpoint::new(640, None, 6),
+        ]);
+        assert_eq!(
+            css,
+            format!(
+                ".photo-grid {{ display: grid; }}\n\
+                 @media (max-width: 639.98px) {{ {} }}\n\
+                 @media (min-width: 640px) {{ {} }}\n",
+                rules(0, 3),
+                rules(1, 6)
+            )
+        );
+
+        // a breakpoint matching every viewport needs no media query
+        let css = grid_breakpoints_css(&[Breakpoint::new(0, None, 4)]);
+        assert_eq!(
+            css,
+            format!(".photo-grid {{ display: grid; }}\n{}\n", rules(0, 4))
+        );
     }
 }
@@ -0,0 +1,61 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use grid::{
+    pack::IntervalFirstFit, ClampConfig, ClampWidthTo, Dimension, FromSize, Grid,
+    RoundedAspectRatio,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct SrcSet {
+    dimensions: Dimension,
+}
+
+#[derive(Deserialize)]
+struct Photo {
+    srcs: Vec<SrcSet>,
+}
+
+/// the sizes the photo page packs at each breakpoint
+fn sizes(width: usize, repeat: usize) -> Vec<Dimension> {
+    let photos: Vec<Photo> = serde_json::from_str(include_str!("../../data.json")).unwrap();
+    photos
+        .iter()
+        .filter_map(|p| p.srcs.iter().map(|s| s.dimensions).max_by_key(|d| d.width))
+        .map(|d| {
+            RoundedAspectRatio::<2>::from_size(&d).clamp_width_to(ClampConfig {
+                min_width: None,
+                max_width: Some(width),
+            })
+        })
+        .collect::<Vec<_>>()
+        .repeat(repeat)
+}
+
+fn add_all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_all");
+    group.sample_size(10);
+    for repeat in [1, 10] {
+        for width in [4, 12] {
+            let data = sizes(width, repeat);
+            let id = format!("{} photos, {width} columns", data.len());
+            group.bench_with_input(BenchmarkId::new("dense", &id), &data, |b, data| {
+                b.iter(|| {
+                    let mut grid = Grid::new(width);
+                    grid.add_all(data.iter().copied());
+                    grid
+                })
+            });
+            group.bench_with_input(BenchmarkId::new("intervals", &id), &data, |b, data| {
+                b.iter(|| {
+                    let mut grid = Grid::new(width);
+                    grid.add_all_with(&IntervalFirstFit, data.iter().copied());
+                    grid
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, add_all);
+criterion_main!(benches);
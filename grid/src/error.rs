@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+use crate::ClampConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum GridError {
+    #[error("invalid clamp configuration {0:?}")]
+    InvalidClamp(ClampConfig),
+    #[error("a grid must have at least 1 row and 1 column, got {width}x{height}")]
+    EmptyGrid { width: usize, height: usize },
+    #[error("a size must not be empty, got {width}x{height}")]
+    EmptySize { width: usize, height: usize },
+    #[error("a size {width} wide does not fit in a grid {columns} wide")]
+    TooWide { width: usize, columns: usize },
+    #[error("{width}x{height} is too small to round with a short edge of {short_edge}")]
+    TooSmall {
+        width: usize,
+        height: usize,
+        short_edge: usize,
+    },
+    #[error("a lookahead window must be between 1 and {max}, got {window}")]
+    InvalidWindow { window: usize, max: usize },
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Coord, Dimension, Size};
+
+/// the lines a grid fills one after another
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    /// rows of a fixed width, growing downwards
+    #[default]
+    Rows,
+    /// columns of a fixed height, growing sideways
+    Columns,
+}
+
+/// the side each line of a grid starts from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+/// how the cells of a grid map onto the screen.
+///
+/// packing always fills rows of a fixed width growing downwards. the flow
+/// transposes and mirrors sizes and positions on the way into and out of
+/// the grid so the packers only need the one implementation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flow {
+    pub axis: Axis,
+    pub direction: Direction,
+}
+
+impl Flow {
+    /// a horizontally scrolling strip of columns
+    pub fn columns() -> Self {
+        Self {
+            axis: Axis::Columns,
+            direction: Direction::LeftToRight,
+        }
+    }
+
+    /// rows filled from the right for right to left locales
+    pub fn right_to_left() -> Self {
+        Self {
+            axis: Axis::Rows,
+            direction: Direction::RightToLeft,
+        }
+    }
+
+    /// convert a size between the screen and the packed orientation
+    pub(crate) fn size(&self, size: &impl Size) -> Dimension {
+        let (width, height) = match self.axis {
+            Axis::Rows => (size.width(), size.height()),
+            Axis::Columns => (size.height(), size.width()),
+        };
+        Dimension { width, height }
+    }
+
+    /// transpose a position between the screen and the packed orientation,
+    /// ignoring the direction lines start from
+    pub(crate) fn origin(&self, origin: Coord<usize>) -> Coord<usize> {
+        match self.axis {
+            Axis::Rows => origin,
+            Axis::Columns => Coord {
+                x: origin.y,
+                y: origin.x,
+            },
+        }
+    }
+
+    /// the screen position and size of an item packed at `origin` in a
+    /// grid with the packed size `grid`
+    pub(crate) fn screen(
+        &self,
+        origin: Coord<usize>,
+        size: &Dimension,
+        grid: &Dimension,
+    ) -> (Coord<usize>, Dimension) {
+        let origin = self.origin(origin);
+        let size = self.size(size);
+        let screen_width = self.size(grid).width;
+        let x = match self.direction {
+            Direction::LeftToRight => origin.x,
+            Direction::RightToLeft => screen_width.saturating_sub(origin.x + size.width),
+        };
+        (Coord { x, ..origin }, size)
+    }
+
+    /// the packed position and size of an item shown at the screen position
+    /// `origin` of a grid with the packed size `grid`, the inverse of
+    /// [`Flow::screen`]. none if the item starts past the screen edge the
+    /// grid fills from
+    pub(crate) fn packed(
+        &self,
+        origin: Coord<usize>,
+        size: &impl Size,
+        grid: &Dimension,
+    ) -> Option<(Coord<usize>, Dimension)> {
+        let screen_width = self.size(grid).width;
+        let x = match self.direction {
+            Direction::LeftToRight => Some(origin.x),
+            Direction::RightToLeft => screen_width.checked_sub(origin.x + size.width()),
+        }?;
+        Some((self.origin(Coord { x, ..origin }), self.size(size)))
+    }
+
+    /// the packed cell shown at the screen cell `cell` of a grid with
+    /// the packed size `grid`
+    pub(crate) fn packed_cell(&self, cell: Coord<usize>, grid: &Dimension) -> Coord<usize> {
+        let screen_width = self.size(grid).width;
+        let x = match self.direction {
+            Direction::LeftToRight => cell.x,
+            Direction::RightToLeft => screen_width - 1 - cell.x,
+        };
+        self.origin(Coord { x, ..cell })
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::Range;
+
+use crate::{Coord, Dimension, Grid, Size};
+
+/// grid storage which keeps the free columns of each row as sorted
+/// intervals instead of a dense vec of cells. free space only ever
+/// shrinks, so once a row is too full for an item of some width it is
+/// never looked at again for that width, keeping packing fast for large
+/// photo sets.
+///
+/// backs [`crate::pack::IntervalFirstFit`]
+#[derive(Debug, Clone)]
+pub(crate) struct IntervalGrid {
+    width: usize,
+    /// free columns in each row, sorted by start
+    rows: Vec<Vec<Range<usize>>>,
+    /// indexed by item width, every row before this one
+    /// has no free interval wide enough for the item
+    first_open: Vec<usize>,
+}
+
+impl IntervalGrid {
+    /// start from the free cells of `grid`, so items already in
+    /// it such as pinned ones are packed around
+    pub(crate) fn from_grid<T>(grid: &Grid<Option<T>>) -> Self {
+        let width = grid.width();
+        let rows = grid
+            .as_slice()
+            .chunks(width)
+            .map(|row| {
+                let mut free: Vec<Range<usize>> = Vec::new();
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, e)| e.is_none())
+                    .for_each(|(x, _)| match free.last_mut() {
+                        Some(last) if last.end == x => last.end += 1,
+                        _ => free.push(x..x + 1),
+                    });
+                free
+            })
+            .collect();
+        Self {
+            width,
+            rows,
+            first_open: vec![0; width + 1],
+        }
+    }
+
+    /// place the item first fit, returning the position of its top left cell.
+    /// items wider than the grid start a new row on their own
+    pub(crate) fn insert(&mut self, size: Dimension) -> Coord<usize> {
+        let origin = self.first_fit(&size).unwrap_or(Coord {
+            x: 0,
+            y: self.rows.len(),
+        });
+        self.insert_at(origin, size);
+        origin
+    }
+
+    /// is the span of columns free in row `y`. rows which have
+    /// not been created yet are free
+    fn is_free(&self, y: usize, columns: &Range<usize>) -> bool {
+        let Some(row) = self.rows.get(y) else {
+            return true;
+        };
+        let idx = row.partition_point(|free| free.end < columns.end);
+        row.get(idx)
+            .is_some_and(|free| free.start <= columns.start && columns.end <= free.end)
+    }
+
+    /// the first position in row major order where the dimension fits
+    fn first_fit(&mut self, dimension: &Dimension) -> Option<Coord<usize>> {
+        let width = dimension.width;
+        let first_open = self.first_open.get_mut(width)?;
+        while self
+            .rows
+            .get(*first_open)
+            .is_some_and(|row| row.iter().all(|free| free.len() < width))
+        {
+            *first_open += 1;
+        }
+
+        (*first_open..self.rows.len()).find_map(|y| {
+            self.rows[y]
+                .iter()
+                .filter(|free| free.len() >= width)
+                .flat_map(|free| free.start..=free.end - width)
+                .find(|x| {
+                    let columns = *x..x + width;
+                    (y + 1..y + dimension.height).all(|y| self.is_free(y, &columns))
+                })
+                .map(|x| Coord { x, y })
+        })
+    }
+
+    fn insert_at(&mut self, origin: Coord<usize>, size: Dimension) {
+        let columns = origin.x..origin.x + size.width;
+        let bottom = origin.y + size.height;
+        let width = self.width;
+        if self.rows.len() < bottom {
+            self.rows
+                .resize_with(bottom, || std::iter::once(0..width).collect());
+        }
+
+        self.rows[origin.y..bottom].iter_mut().for_each(|row| {
+            *row = row
+                .iter()
+                .flat_map(|free| {
+                    [
+                        free.start..columns.start.clamp(free.start, free.end),
+                        columns.end.clamp(free.start, free.end)..free.end,
+                    ]
+                })
+                .filter(|free| !free.is_empty())
+                .collect();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pack::{FirstFit, IntervalFirstFit},
+        GridContent, GridError,
+    };
+
+    /// a deterministic sequence of photo like sizes
+    fn sizes(n: usize) -> Vec<Dimension> {
+        let shapes = [(3, 2), (2, 3), (2, 2), (3, 1), (1, 1), (2, 1), (1, 2)];
+        let mut seed: u64 = 42;
+        (0..n)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let (width, height) = shapes[(seed >> 33) as usize % shapes.len()];
+                Dimension { width, height }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_should_match_dense_first_fit() {
+        [3, 4, 6, 8, 12].into_iter().for_each(|width| {
+            let data = sizes(200);
+
+            let mut dense = Grid::new(width);
+            dense.add_all_with(&FirstFit, data.clone());
+            let mut intervals = Grid::new(width);
+            intervals.add_all_with(&IntervalFirstFit, data);
+
+            assert_eq!(dense.as_slice(), intervals.as_slice());
+        })
+    }
+
+    #[test]
+    fn it_should_pack_around_pinned_items() {
+        let pinned = [GridContent::new(
+            100,
+            Coord { x: 1, y: 0 },
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+        )];
+        let data = sizes(50);
+
+        let mut dense = Grid::new(6);
+        assert!(dense.pin(pinned).is_empty());
+        dense.add_all_with(&FirstFit, data.clone());
+        let mut intervals = Grid::new(6);
+        assert!(intervals.pin(pinned).is_empty());
+        intervals.add_all_with(&IntervalFirstFit, data);
+
+        assert_eq!(dense.as_slice(), intervals.as_slice());
+    }
+
+    #[test]
+    fn it_should_reject_items_wider_than_the_grid() {
+        let data = [
+            Dimension {
+                width: 2,
+                height: 1,
+            },
+            Dimension {
+                width: 5,
+                height: 1,
+            },
+        ];
+        let err = Err(GridError::TooWide {
+            width: 5,
+            columns: 4,
+        });
+
+        let mut dense = Grid::new(4);
+        assert_eq!(dense.try_add_all_with(&FirstFit, data), err);
+        let mut intervals = Grid::new(4);
+        assert_eq!(intervals.try_add_all_with(&IntervalFirstFit, data), err);
+
+        assert!(intervals.as_slice().is_empty());
+        assert_eq!(dense.as_slice(), intervals.as_slice());
+    }
+
+    #[test]
+    fn it_should_split_intervals() {
+        let mut grid = IntervalGrid::from_grid(&Grid::<Option<usize>>::new(6));
+        grid.insert(Dimension {
+            width: 2,
+            height: 2,
+        });
+        grid.insert_at(
+            Coord { x: 3, y: 1 },
+            Dimension {
+                width: 1,
+                height: 1,
+            },
+        );
+
+        assert_eq!(grid.rows[1], [2..3, 4..6]);
+        assert!(grid.is_free(0, &(2..6)));
+        assert!(!grid.is_free(0, &(1..3)));
+        assert!(!grid.is_free(1, &(2..4)));
+        assert!(grid.is_free(1, &(4..6)));
+        assert!(grid.is_free(2, &(0..6)));
+    }
+
+    #[test]
+    fn it_should_start_from_the_free_cells() {
+        let mut dense = Grid::new(4);
+        dense.add_all([
+            Dimension {
+                width: 1,
+                height: 1,
+            },
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+        ]);
+
+        let grid = IntervalGrid::from_grid(&dense);
+
+        assert_eq!(grid.rows.len(), 2);
+        assert!(grid.is_free(0, &(3..4)));
+        assert!(!grid.is_free(0, &(0..3)));
+        assert!(grid.is_free(1, &(0..1)));
+        assert!(!grid.is_free(1, &(1..3)));
+        assert!(grid.is_free(1, &(3..4)));
+    }
+}
@@ -1,12 +1,23 @@
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
-    collections::HashSet,
+    collections::BTreeMap,
     fmt::Display,
-    ops::{Add, Range, RangeInclusive},
+    num::NonZeroUsize,
+    ops::{Add, Range},
 };
+mod error;
+pub mod flow;
+mod interval;
+pub mod pack;
 #[cfg(feature = "parse")]
 pub mod parse;
+#[cfg(test)]
+mod proptests;
+
+pub use error::GridError;
+use flow::Flow;
+use pack::{FirstFit, PackingStrategy};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Dimension {
@@ -43,23 +54,17 @@ pub struct Coord<T> {
     pub y: T,
 }
 
-impl<T> Coord<T>
-where
-    T: Copy,
-    RangeInclusive<T>: Iterator<Item = T>,
-{
-    /// given a bottom right coord, iterate over all coords within the boundary
-    fn iter_area<'a>(&'a self, bottom_right: &'a Self) -> impl Iterator<Item = Coord<T>> + 'a {
-        (self.y..=bottom_right.y)
-            .flat_map(|y| (self.x..=bottom_right.x).map(move |x| Coord { x, y }))
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct Grid<T> {
     width: usize,
     height: usize,
     contents: Vec<T>,
+    /// the index of the top left cell and the size of every inserted
+    /// item, keyed by its id as stored in the cells it covers
+    placed: BTreeMap<T, (usize, Dimension)>,
+    /// width, height and every position stored in the grid are in the
+    /// packed orientation, the flow maps them onto the screen
+    flow: Flow,
 }
 
 impl<T> Size for Grid<T> {
@@ -166,6 +171,29 @@ impl<T> Grid<T> {
             width,
             height: 0,
             contents: Vec::new(),
+            placed: BTreeMap::new(),
+            flow: Flow::default(),
+        }
+    }
+
+    /// a grid filling lines of `extent` cells in the order given by `flow`.
+    /// the extent is the width of rows or the height of columns
+    pub fn new_with_flow(extent: usize, flow: Flow) -> Self {
+        Self {
+            flow,
+            ..Self::new(extent)
+        }
+    }
+
+    pub fn flow(&self) -> Flow {
+        self.flow
+    }
+
+    /// the size of the grid in the packed orientation
+    fn packed_size(&self) -> Dimension {
+        Dimension {
+            width: self.width,
+            height: self.height,
         }
     }
 
@@ -242,35 +270,6 @@ impl<T> Grid<T> {
         cb(self.to_index(c), &mut self.contents)
     }
 }
-impl<T> Grid<T>
-where
-    T: Eq,
-{
-    fn touching(&self, top_left: &Coord<usize>) -> Coord<usize> {
-        let val = self.contents.get(self.to_index(*top_left)).unwrap();
-        let right_extent = (top_left.x..self.width)
-            .map(|x| Coord { y: top_left.y, x })
-            .take_while(|coord| {
-                self.contents
-                    .get(self.to_index(*coord))
-                    .is_some_and(|v| v == val)
-            })
-            .last();
-        let bottom_extent = (top_left.y..)
-            .map(|y| Coord { y, x: top_left.x })
-            .take_while(|coord| {
-                self.contents
-                    .get(self.to_index(*coord))
-                    .is_some_and(|v| v == val)
-            })
-            .last();
-        match (right_extent, bottom_extent) {
-            (Some(Coord { x, .. }), Some(Coord { y, .. })) => Coord { x, y },
-            _ => dbg!(*top_left),
-        }
-    }
-}
-
 impl<T> Grid<T>
 where
     T: Default,
@@ -285,10 +284,20 @@ where
         let cur = self.contents.len();
         let iter = (0..end_coord.saturating_sub(cur)).map(|_| T::default());
         self.contents.extend(iter);
-        self.height = out_height;
+        self.height = self.height.max(out_height);
     }
 
+    /// # Panics
+    ///
+    /// panics if the width or height is 0, see [`Grid::try_new_with_height`]
     pub fn new_with_height(width: usize, height: usize) -> Self {
+        Self::try_new_with_height(width, height).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_new_with_height(width: usize, height: usize) -> Result<Self, GridError> {
+        if width == 0 || height == 0 {
+            return Err(GridError::EmptyGrid { width, height });
+        }
         let mut out = Self::new(width);
         let idx = out.to_index(Coord {
             x: 0,
@@ -296,13 +305,13 @@ where
         });
         out.extend_to(idx);
 
-        out
+        Ok(out)
     }
 }
 
 impl<T> Grid<Option<T>>
 where
-    T: Clone,
+    T: Clone + Ord,
 {
     /// iterate over the unoccupied spaces
     fn available(&self) -> impl Iterator<Item = usize> + '_ {
@@ -328,8 +337,11 @@ where
             })
     }
 
+    /// fill the cells covered by the item with its id. an id which is
+    /// already in the grid is moved, so every id covers one rectangle
     fn insert_at(&mut self, index: usize, id: T, dimension: &impl Size) -> Result<(), ()> {
         let last = self.dimensions_iter(dimension, index).last().ok_or(())?;
+        self.take(&id);
         self.extend_to(last);
         self.dimensions_iter(dimension, index)
             .collect::<Vec<_>>()
@@ -340,30 +352,239 @@ where
                 };
                 *e = Some(id.clone())
             });
+        self.placed.insert(
+            Some(id),
+            (
+                index,
+                Dimension {
+                    width: dimension.width(),
+                    height: dimension.height(),
+                },
+            ),
+        );
         Ok(())
     }
+
+    /// empty the cells of the item with `id`, returning the index
+    /// of its top left cell and its size
+    fn take(&mut self, id: &T) -> Option<(usize, Dimension)> {
+        let (index, size) = self.placed.remove(&Some(id.clone()))?;
+        self.dimensions_iter(&size, index)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|idx| {
+                if let Some(e) = self.contents.get_mut(idx) {
+                    *e = None
+                }
+            });
+        Some((index, size))
+    }
+
+    /// every inserted item with the index of its top left cell,
+    /// in the order of that cell along the flow of the grid
+    fn placements(&self) -> Vec<(T, usize, Dimension)> {
+        let mut placed: Vec<(T, usize, Dimension)> = self
+            .placed
+            .iter()
+            .filter_map(|(id, (index, size))| Some((id.clone()?, *index, *size)))
+            .collect();
+        placed.sort_by_key(|(_, index, _)| *index);
+        placed
+    }
+
+    /// insert the item with its top left cell at the screen position
+    /// `origin`, as yielded when iterating the grid. returns false without
+    /// changing the grid if any cell it would cover is occupied.
+    ///
+    /// right to left columns grow towards the left of the screen, so there
+    /// `origin` is measured from the left edge of the grid as it stands and
+    /// items further left than that edge do not fit
+    pub fn place(&mut self, id: T, origin: Coord<usize>, dimension: &impl Size) -> bool {
+        let Some((origin, dimension)) = self.flow.packed(origin, dimension, &self.packed_size())
+        else {
+            return false;
+        };
+        let index = self.to_index(origin);
+        self.does_fit_at(index, &dimension) && self.insert_at(index, id, &dimension).is_ok()
+    }
+
+    /// place items at fixed positions, typically before packing the rest.
+    /// packers treat the pinned cells as occupied and flow everything else
+    /// around them. pinned items which overlap one placed before them, or
+    /// run off the side of the grid, are skipped and returned
+    pub fn pin(&mut self, pinned: impl IntoIterator<Item = GridContent<T>>) -> Vec<GridContent<T>> {
+        pinned
+            .into_iter()
+            .filter(|c| !self.place(c.data.clone(), c.origin, &c.size))
+            .collect()
+    }
+
+    /// slide every item back into the empty cells before it in its
+    /// column, or row for a column flow, then drop the empty lines left
+    /// at the end of the grid
+    pub fn compact(&mut self) {
+        // items are moved in row major order so everything above an
+        // item has already been moved by the time it is reached
+        self.placements().into_iter().for_each(|(id, index, size)| {
+            self.take(&id);
+            let mut target = index;
+            while target >= self.width && self.does_fit_at(target - self.width, &size) {
+                target -= self.width;
+            }
+            self.insert_at(target, id, &size)
+                .expect("failed to insert at valid location");
+        });
+
+        let height = self
+            .placed
+            .values()
+            .map(|(index, size)| self.to_dimension(*index).y + size.height)
+            .max()
+            .unwrap_or_default();
+        self.contents.truncate(height * self.width);
+        self.height = height;
+    }
+
+    /// remove the item with `id`, leaving its cells empty
+    pub fn remove(&mut self, id: &T) -> Option<GridContent<T>> {
+        let (index, size) = self.take(id)?;
+        let (origin, size) = self
+            .flow
+            .screen(self.to_dimension(index), &size, &self.packed_size());
+        Some(GridContent {
+            data: id.clone(),
+            size,
+            origin,
+        })
+    }
+}
+
+/// check an item can be placed on a grid `columns` wide
+pub(crate) fn check_fits(el: &Dimension, columns: usize) -> Result<(), GridError> {
+    match (el.width, el.height) {
+        (0, _) | (_, 0) => Err(GridError::EmptySize {
+            width: el.width,
+            height: el.height,
+        }),
+        (width, _) if width > columns => Err(GridError::TooWide { width, columns }),
+        _ => Ok(()),
+    }
 }
 
 impl Grid<Option<usize>> {
+    /// # Panics
+    ///
+    /// panics if any item is empty or wider than the grid, see [`Grid::try_add_all`]
     pub fn add_all<T>(&mut self, data: impl IntoIterator<Item = T>)
     where
         T: Size,
     {
-        data.into_iter().enumerate().for_each(|(idx, el)| {
-            let fit = self.available().find(|e| self.does_fit_at(*e, &el));
+        self.add_all_with(&FirstFit, data)
+    }
 
-            match fit {
-                Some(a) => {
-                    self.insert_at(a, idx, &el)
-                        .expect("failed to insert at valid location");
-                }
-                None => {
-                    let len = self.contents.len();
-                    self.extend_to(len);
-                    self.insert_at(len, idx, &el)
-                        .expect("failed to insert after extending");
-                }
+    pub fn try_add_all<T>(&mut self, data: impl IntoIterator<Item = T>) -> Result<(), GridError>
+    where
+        T: Size,
+    {
+        self.try_add_all_with(&FirstFit, data)
+    }
+
+    /// place every item using the provided packing strategy.
+    /// each item is identified in the grid by its index in `data`
+    ///
+    /// # Panics
+    ///
+    /// panics if any item is empty or wider than the grid, see [`Grid::try_add_all_with`]
+    pub fn add_all_with<T>(
+        &mut self,
+        strategy: &impl PackingStrategy,
+        data: impl IntoIterator<Item = T>,
+    ) where
+        T: Size,
+    {
+        self.try_add_all_with(strategy, data)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// place every item using the provided packing strategy. nothing is
+    /// placed if any item is empty or wider than the grid
+    pub fn try_add_all_with<T>(
+        &mut self,
+        strategy: &impl PackingStrategy,
+        data: impl IntoIterator<Item = T>,
+    ) -> Result<(), GridError>
+    where
+        T: Size,
+    {
+        let data: Vec<Dimension> = data.into_iter().map(|el| self.flow.size(&el)).collect();
+        data.iter().try_for_each(|el| check_fits(el, self.width))?;
+        strategy.pack(self, &data);
+        Ok(())
+    }
+
+    /// # Panics
+    ///
+    /// panics if the item is empty or wider than the grid, see [`Grid::try_insert`]
+    pub fn insert(&mut self, id: usize, el: &impl Size) {
+        self.try_insert(id, el).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// insert the item at the first position it fits,
+    /// extending the grid if there is no room
+    pub fn try_insert(&mut self, id: usize, el: &impl Size) -> Result<(), GridError> {
+        let el = self.flow.size(el);
+        check_fits(&el, self.width)?;
+        self.insert_first_fit(id, &el);
+        Ok(())
+    }
+
+    /// insert the item at the first available index it fits,
+    /// extending the grid if there is no room
+    fn insert_first_fit(&mut self, id: usize, el: &impl Size) {
+        let fit = self.available().find(|e| self.does_fit_at(*e, el));
+
+        match fit {
+            Some(a) => {
+                self.insert_at(a, id, el)
+                    .expect("failed to insert at valid location");
+            }
+            None => {
+                let len = self.contents.len();
+                self.extend_to(len);
+                self.insert_at(len, id, el)
+                    .expect("failed to insert after extending");
             }
+        }
+    }
+
+    /// number of unoccupied cells in the grid
+    fn empty_cells(&self) -> usize {
+        self.available().count()
+    }
+}
+
+/// renders the occupancy of each cell as it appears on screen with the
+/// id of the item occupying it, or `.` for empty cells
+impl<T> Display for Grid<Option<T>>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let packed = self.packed_size();
+        let screen = self.flow.size(&packed);
+        let cells: Vec<String> = screen
+            .coords_iter()
+            .map(|cell| self.flow.packed_cell(cell, &packed))
+            .map(|cell| match self.contents.get(self.to_index(cell)) {
+                Some(Some(id)) => id.to_string(),
+                _ => ".".to_string(),
+            })
+            .collect();
+        let pad = cells.iter().map(|c| c.len()).max().unwrap_or_default();
+
+        cells.chunks(screen.width.max(1)).try_for_each(|row| {
+            let row: Vec<String> = row.iter().map(|c| format!("{c:>pad$}")).collect();
+            writeln!(f, "{}", row.join(" "))
         })
     }
 }
@@ -376,6 +597,10 @@ pub struct GridContent<T> {
 }
 
 impl<T> GridContent<T> {
+    pub fn new(data: T, origin: Coord<usize>, size: Dimension) -> Self {
+        Self { data, size, origin }
+    }
+
     pub fn map<U>(self, cb: impl FnOnce(T) -> U) -> GridContent<U> {
         let GridContent { data, size, origin } = self;
         let data = cb(data);
@@ -395,64 +620,37 @@ impl<T> GridContent<T> {
     }
 }
 
+/// yields the items inserted into a grid in the order of their first cell
+/// along the flow of the grid
 pub struct GridVisitor<T> {
-    seen: HashSet<usize>,
-    grid: Grid<T>,
-    cur: usize,
+    placed: std::vec::IntoIter<(T, usize, Dimension)>,
+    grid: Grid<Option<T>>,
 }
 
-impl<T> Iterator for GridVisitor<Option<T>>
-where
-    T: Eq + Copy,
-{
+impl<T> Iterator for GridVisitor<T> {
     type Item = GridContent<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.seen.contains(&self.cur) {
-            self.cur += 1;
-            return self.next();
-        }
-        let this = self.grid.contents.get(self.cur)?;
-        let coord = self.grid.to_dimension(self.cur);
-
-        let bottom_right = self.grid.touching(&coord);
-
-        self.seen.extend(
-            coord
-                .iter_area(&bottom_right)
-                .map(|coord| self.grid.to_index(coord)),
-        );
-
-        let dimension = Dimension {
-            width: 1 + bottom_right.x - coord.x,
-            height: 1 + bottom_right.y - coord.y,
-        };
-
-        self.cur += dimension.width;
-
-        match this {
-            Some(val) => Some(GridContent {
-                data: *val,
-                size: dimension,
-                origin: coord,
-            }),
-            None => self.next(),
-        }
+        let (data, idx, size) = self.placed.next()?;
+        let (origin, size) =
+            self.grid
+                .flow
+                .screen(self.grid.to_dimension(idx), &size, &self.grid.packed_size());
+        Some(GridContent { data, size, origin })
     }
 }
 
 impl<T> IntoIterator for Grid<Option<T>>
 where
-    T: Eq + Copy,
+    T: Clone + Ord,
 {
     type Item = GridContent<T>;
 
-    type IntoIter = GridVisitor<Option<T>>;
+    type IntoIter = GridVisitor<T>;
 
     fn into_iter(self) -> Self::IntoIter {
         GridVisitor {
-            cur: 0,
-            seen: HashSet::new(),
+            placed: self.placements().into_iter(),
             grid: self,
         }
     }
@@ -483,6 +681,13 @@ pub trait FromSize {
         T: Size;
 }
 
+/// a [`FromSize`] which rejects sizes it can not represent
+pub trait TryFromSize: Sized {
+    fn try_from_size<T>(ratio: &T) -> Result<Self, GridError>
+    where
+        T: Size;
+}
+
 impl FromSize for Orientation {
     fn from_size<T>(ratio: &T) -> Self
     where
@@ -553,7 +758,19 @@ impl Size for NormalizedAspectRatio {
 }
 
 impl FromSize for NormalizedAspectRatio {
+    /// # Panics
+    ///
+    /// panics on an empty size, see [`TryFromSize::try_from_size`]
     fn from_size<T>(ratio: &T) -> Self
+    where
+        T: Size,
+    {
+        Self::try_from_size(ratio).unwrap_or_else(|e| panic!("{e}"))
+    }
+}
+
+impl TryFromSize for NormalizedAspectRatio {
+    fn try_from_size<T>(ratio: &T) -> Result<Self, GridError>
     where
         T: Size,
     {
@@ -563,16 +780,22 @@ impl FromSize for NormalizedAspectRatio {
             Orientation::Portrait => (ratio.width(), ratio.height()),
             Orientation::Landscape => (ratio.height(), ratio.width()),
         };
+        if min == 0 {
+            return Err(GridError::EmptySize {
+                width: ratio.width(),
+                height: ratio.height(),
+            });
+        }
 
         let mut long_edge = max / min;
         if max % min > 0 {
             long_edge += 1;
         }
 
-        Self {
+        Ok(Self {
             orientation,
             long_edge,
-        }
+        })
     }
 }
 
@@ -598,11 +821,59 @@ impl<const SIZE: usize> Size for RoundedAspectRatio<SIZE> {
     }
 }
 
+impl<const SIZE: usize> TryFromSize for RoundedAspectRatio<SIZE> {
+    fn try_from_size<T>(ratio: &T) -> Result<Self, GridError>
+    where
+        T: Size,
+    {
+        let Rounded {
+            long_edge,
+            orientation,
+            ..
+        } = Rounded::try_new(ratio, SIZE)?;
+
+        Ok(Self {
+            orientation,
+            long_edge,
+        })
+    }
+}
+
 impl<const SIZE: usize> FromSize for RoundedAspectRatio<SIZE> {
     fn from_size<T>(ratio: &T) -> Self
     where
         T: Size,
     {
+        let Rounded {
+            long_edge,
+            orientation,
+            ..
+        } = Rounded::new(
+            ratio,
+            NonZeroUsize::new(SIZE).expect("a rounded aspect ratio must span at least 1 cell"),
+        );
+
+        Self {
+            orientation,
+            long_edge,
+        }
+    }
+}
+
+/// an aspect ratio rounded so the short edge spans a number of cells
+/// chosen at runtime. [`RoundedAspectRatio`] fixes it at compile time
+#[derive(Debug, Clone, Copy)]
+pub struct Rounded {
+    short_edge: usize,
+    long_edge: usize,
+    orientation: Orientation,
+}
+
+impl Rounded {
+    /// sizes with a short edge below `short_edge` pixels round to
+    /// nonsense, see [`Rounded::try_new`]
+    pub fn new(ratio: &impl Size, short_edge: NonZeroUsize) -> Self {
+        let short_edge = short_edge.get();
         let orientation = Orientation::from_size(ratio);
 
         let (min, max) = match orientation {
@@ -610,7 +881,7 @@ impl<const SIZE: usize> FromSize for RoundedAspectRatio<SIZE> {
             Orientation::Landscape => (ratio.height(), ratio.width()),
         };
 
-        let divisor = min / SIZE;
+        let divisor = (min / short_edge).max(1);
 
         let mut long_edge = max / divisor;
         if max % divisor > divisor / 2 {
@@ -618,15 +889,162 @@ impl<const SIZE: usize> FromSize for RoundedAspectRatio<SIZE> {
         }
 
         Self {
-            orientation,
+            short_edge,
             long_edge,
+            orientation,
+        }
+    }
+}
+
+impl Rounded {
+    pub fn try_new(ratio: &impl Size, short_edge: usize) -> Result<Self, GridError> {
+        let (width, height) = (ratio.width(), ratio.height());
+        if width == 0 || height == 0 {
+            return Err(GridError::EmptySize { width, height });
+        }
+        match NonZeroUsize::new(short_edge) {
+            Some(short_edge) if width.min(height) >= short_edge.get() => {
+                Ok(Self::new(ratio, short_edge))
+            }
+            _ => Err(GridError::TooSmall {
+                width,
+                height,
+                short_edge,
+            }),
+        }
+    }
+}
+
+impl Size for Rounded {
+    fn width(&self) -> usize {
+        match self.orientation {
+            Orientation::Portrait => self.short_edge,
+            Orientation::Landscape => self.long_edge,
         }
     }
+
+    fn height(&self) -> usize {
+        match self.orientation {
+            Orientation::Portrait => self.long_edge,
+            Orientation::Landscape => self.short_edge,
+        }
+    }
+}
+
+/// relative difference between the aspect ratios of `a` and `b`
+pub fn aspect_error(a: &impl Size, b: &impl Size) -> f64 {
+    let a = a.width() as f64 / a.height() as f64;
+    let b = b.width() as f64 / b.height() as f64;
+    a.max(b) / a.min(b) - 1.0
+}
+
+/// a rounding precision for a set of sizes and the
+/// aspect error it introduces
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Precision {
+    /// the number of cells spanned by the short edge
+    pub short_edge: usize,
+    /// the mean [`aspect_error`] over all the sizes
+    pub mean_error: f64,
+    /// the largest [`aspect_error`] of any size
+    pub max_error: f64,
+}
+
+impl Precision {
+    /// measure the error of rounding every size with `short_edge`
+    pub fn measure<'a, T>(sizes: impl IntoIterator<Item = &'a T>, short_edge: NonZeroUsize) -> Self
+    where
+        T: Size + 'a,
+    {
+        let (count, sum, max_error) =
+            sizes
+                .into_iter()
+                .fold((0, 0.0, 0.0_f64), |(count, sum, max), size| {
+                    let error = aspect_error(&Rounded::new(size, short_edge), size);
+                    (count + 1, sum + error, max.max(error))
+                });
+
+        Self {
+            short_edge: short_edge.get(),
+            mean_error: match count {
+                0 => 0.0,
+                count => sum / count as f64,
+            },
+            max_error,
+        }
+    }
+
+    /// the candidate short edge with the smallest mean error.
+    /// ties go to the smaller short edge
+    pub fn best<T>(sizes: &[T], candidates: impl IntoIterator<Item = usize>) -> Option<Self>
+    where
+        T: Size,
+    {
+        candidates
+            .into_iter()
+            .filter_map(NonZeroUsize::new)
+            .map(|short_edge| Self::measure(sizes, short_edge))
+            .fold(None, |best: Option<Self>, p| match best {
+                Some(best) if best.mean_error <= p.mean_error => Some(best),
+                _ => Some(p),
+            })
+    }
+}
+
+/// a size scaled up by a whole number so it takes a larger
+/// footprint with exactly the same aspect ratio
+#[derive(Debug, Clone, Copy)]
+pub struct Emphasized<T> {
+    pub size: T,
+    /// a factor of 0 is treated as 1
+    pub factor: usize,
+}
+
+impl<T> Emphasized<T>
+where
+    T: Size,
+{
+    /// lower the factor to the largest one whose width is at most
+    /// `max_width`, or 1 if none is. clamping the width afterwards
+    /// would change the aspect ratio, this keeps it
+    pub fn within(self, max_width: Option<usize>) -> Self {
+        let factor = (1..=self.factor.max(1))
+            .rev()
+            .find(|factor| max_width.is_none_or(|max| self.size.width() * factor <= max))
+            .unwrap_or(1);
+        Self { factor, ..self }
+    }
+}
+
+impl<T> Size for Emphasized<T>
+where
+    T: Size,
+{
+    fn width(&self) -> usize {
+        self.size.width() * self.factor.max(1)
+    }
+
+    fn height(&self) -> usize {
+        self.size.height() * self.factor.max(1)
+    }
+}
+
+pub trait Emphasize: Sized {
+    fn emphasize(self, factor: usize) -> Emphasized<Self>;
+}
+
+impl<T> Emphasize for T
+where
+    T: Size,
+{
+    fn emphasize(self, factor: usize) -> Emphasized<Self> {
+        Emphasized { size: self, factor }
+    }
 }
 
 /// configuration to prevent a dimension from going outside
 /// of these bounds
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClampConfig {
     /// constrain the minimum width
     pub min_width: Option<usize>,
@@ -635,7 +1053,13 @@ pub struct ClampConfig {
 }
 
 pub trait ClampWidthTo {
+    /// # Panics
+    ///
+    /// panics if the size would need clamping by both the minimum and the
+    /// maximum width or is empty, see [`ClampWidthTo::try_clamp_width_to`]
     fn clamp_width_to(self, clamp_config: ClampConfig) -> Dimension;
+
+    fn try_clamp_width_to(self, clamp_config: ClampConfig) -> Result<Dimension, GridError>;
 }
 
 impl<T> ClampWidthTo for T
@@ -643,8 +1067,16 @@ where
     T: Size,
 {
     fn clamp_width_to(self, clamp_config: ClampConfig) -> Dimension {
+        self.try_clamp_width_to(clamp_config)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_clamp_width_to(self, clamp_config: ClampConfig) -> Result<Dimension, GridError> {
         let height = self.height();
         let width = self.width();
+        if width == 0 || height == 0 {
+            return Err(GridError::EmptySize { width, height });
+        }
 
         let max_is_clamped = clamp_config
             .max_width
@@ -654,10 +1086,10 @@ where
             .and_then(|min| (width < min).then_some(min));
 
         let clamped_to_width = match (min_is_clamped, max_is_clamped) {
-            (Some(_), Some(_)) => panic!("Invalid clamp configuration {clamp_config:?}"),
+            (Some(_), Some(_)) => return Err(GridError::InvalidClamp(clamp_config)),
             (None, None) => {
                 // we do dont need to do anything, return
-                return Dimension { width, height };
+                return Ok(Dimension { width, height });
             }
             (Some(min), None) => min,
             (None, Some(max)) => max,
@@ -669,10 +1101,10 @@ where
         if new_height < 1 {
             new_height = 1;
         }
-        Dimension {
+        Ok(Dimension {
             width: clamped_to_width,
             height: new_height,
-        }
+        })
     }
 }
 
@@ -680,7 +1112,9 @@ where
 mod tests {
     use super::*;
     use cool_asserts::assert_matches;
-    use std::ops::Not;
+    use pack::{BestFit, Skyline};
+    use proptest::prelude::*;
+    use std::{collections::HashSet, ops::Not};
 
     #[test]
     fn it_should_return_3_to_2_for_z6_dimensions() {
@@ -797,6 +1231,28 @@ mod tests {
         )
     }
 
+    #[test]
+    fn it_should_display_occupancy() {
+        let data =
+            [(2, 1), (2, 1), (1, 2), (1, 2)].map(|(width, height)| Dimension { width, height });
+        let mut grid = Grid::new(4);
+        grid.add_all(data);
+
+        assert_eq!(grid.to_string(), "0 0 1 1\n2 3 . .\n2 3 . .\n");
+    }
+
+    #[test]
+    fn it_should_pad_ids() {
+        let data = [Dimension {
+            width: 1,
+            height: 1,
+        }; 11];
+        let mut grid = Grid::new(4);
+        grid.add_all(data);
+
+        assert_eq!(grid.to_string(), " 0  1  2  3\n 4  5  6  7\n 8  9 10  .\n");
+    }
+
     #[test]
     fn off_by_1_aspect() {
         let data = RoundedAspectRatio::<2>::from_size(&crate::Dimension {
@@ -813,6 +1269,138 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_should_round_at_runtime_precision() {
+        let z6 = crate::Dimension {
+            width: 6048,
+            height: 4024,
+        };
+        let a = Rounded::new(&z6, NonZeroUsize::new(2).unwrap());
+        assert_eq!((a.width(), a.height()), (3, 2));
+        let a = Rounded::new(&z6, NonZeroUsize::new(4).unwrap());
+        assert_eq!((a.width(), a.height()), (6, 4));
+    }
+
+    #[test]
+    fn it_should_pick_the_most_accurate_precision() {
+        // 4:5 portraits round to a square with a short edge of 2 but are exact with 4
+        let sizes = [crate::Dimension {
+            width: 1600,
+            height: 2000,
+        }];
+        let best = Precision::best(&sizes, [2, 3, 4]).unwrap();
+        assert_eq!(best.short_edge, 4);
+        assert_eq!(best.max_error, 0.0);
+
+        let coarse = Precision::measure(&sizes, NonZeroUsize::new(2).unwrap());
+        assert!((coarse.mean_error - 0.25).abs() < 1e-9, "{coarse:?}");
+    }
+
+    #[test]
+    fn it_should_emphasize_within_clamp() {
+        let rounded = Rounded::new(
+            &crate::Dimension {
+                width: 6048,
+                height: 4024,
+            },
+            NonZeroUsize::new(2).unwrap(),
+        );
+        let emphasized = rounded.emphasize(2);
+        assert_eq!((emphasized.width(), emphasized.height()), (6, 4));
+        let clamp = ClampConfig {
+            max_width: Some(4),
+            min_width: None,
+        };
+
+        // a 4 column grid has no room for 6x4 so the photo keeps its 3x2 size
+        let within = emphasized.within(clamp.max_width);
+        assert_eq!(within.factor, 1);
+        assert_matches!(
+            within.clamp_width_to(clamp),
+            crate::Dimension {
+                width: 3,
+                height: 2
+            }
+        );
+        assert_eq!(emphasized.within(Some(6)).factor, 2);
+        assert_eq!(emphasized.within(None).factor, 2);
+    }
+
+    #[test]
+    fn it_should_reject_invalid_input() {
+        let empty = crate::Dimension {
+            width: 0,
+            height: 10,
+        };
+        let clamp = ClampConfig {
+            min_width: Some(4),
+            max_width: Some(2),
+        };
+
+        assert_matches!(
+            crate::Dimension {
+                width: 3,
+                height: 2
+            }
+            .try_clamp_width_to(clamp),
+            Err(GridError::InvalidClamp(c)) if c == clamp
+        );
+        assert_matches!(
+            Grid::<Option<usize>>::try_new_with_height(4, 0),
+            Err(GridError::EmptyGrid {
+                width: 4,
+                height: 0
+            })
+        );
+        assert_matches!(
+            NormalizedAspectRatio::try_from_size(&empty),
+            Err(GridError::EmptySize { .. })
+        );
+        assert_matches!(
+            RoundedAspectRatio::<2>::try_from_size(&crate::Dimension {
+                width: 1,
+                height: 10
+            }),
+            Err(GridError::TooSmall { short_edge: 2, .. })
+        );
+        assert_matches!(
+            Rounded::try_new(
+                &crate::Dimension {
+                    width: 10,
+                    height: 10
+                },
+                0
+            ),
+            Err(GridError::TooSmall { short_edge: 0, .. })
+        );
+
+        let mut grid = Grid::new(4);
+        assert_eq!(
+            grid.try_add_all([
+                crate::Dimension {
+                    width: 1,
+                    height: 1
+                },
+                crate::Dimension {
+                    width: 5,
+                    height: 1
+                }
+            ]),
+            Err(GridError::TooWide {
+                width: 5,
+                columns: 4
+            })
+        );
+        assert_eq!(
+            grid.try_add_all([empty]),
+            Err(GridError::EmptySize {
+                width: 0,
+                height: 10
+            })
+        );
+        assert!(grid.as_slice().is_empty());
+    }
+
     #[test]
     fn it_should_handle_panorama() {
         assert_matches!(
@@ -890,4 +1478,419 @@ mod tests {
             ]
         );
     }
+
+    /// the recursive visitor which [`GridVisitor`] replaced
+    fn legacy_visit(grid: &Grid<Option<usize>>) -> Vec<GridContent<usize>> {
+        fn bottom_right(grid: &Grid<Option<usize>>, top_left: &Coord<usize>) -> Coord<usize> {
+            let val = grid.contents.get(grid.to_index(*top_left)).unwrap();
+            let matches = |coord: &Coord<usize>| {
+                grid.contents
+                    .get(grid.to_index(*coord))
+                    .is_some_and(|v| v == val)
+            };
+            let x = (top_left.x..grid.width)
+                .map(|x| Coord { y: top_left.y, x })
+                .take_while(matches)
+                .last()
+                .unwrap()
+                .x;
+            let y = (top_left.y..)
+                .map(|y| Coord { y, x: top_left.x })
+                .take_while(matches)
+                .last()
+                .unwrap()
+                .y;
+            Coord { x, y }
+        }
+
+        fn visit(
+            grid: &Grid<Option<usize>>,
+            seen: &mut HashSet<usize>,
+            cur: usize,
+            out: &mut Vec<GridContent<usize>>,
+        ) {
+            if seen.contains(&cur) {
+                return visit(grid, seen, cur + 1, out);
+            }
+            let Some(this) = grid.contents.get(cur) else {
+                return;
+            };
+            let coord = grid.to_dimension(cur);
+            let bottom_right = bottom_right(grid, &coord);
+            (coord.y..=bottom_right.y)
+                .flat_map(|y| (coord.x..=bottom_right.x).map(move |x| Coord { x, y }))
+                .for_each(|c| {
+                    seen.insert(grid.to_index(c));
+                });
+            let size = Dimension {
+                width: 1 + bottom_right.x - coord.x,
+                height: 1 + bottom_right.y - coord.y,
+            };
+            if let Some(data) = this {
+                out.push(GridContent {
+                    data: *data,
+                    size,
+                    origin: coord,
+                });
+            }
+            visit(grid, seen, cur + size.width, out)
+        }
+
+        let mut out = Vec::new();
+        visit(grid, &mut HashSet::new(), 0, &mut out);
+        out
+    }
+
+    fn summary(contents: &[GridContent<usize>]) -> Vec<(usize, Coord<usize>, (usize, usize))> {
+        contents
+            .iter()
+            .map(|c| (c.data, c.origin, (c.size.width, c.size.height)))
+            .collect()
+    }
+
+    fn sizes() -> impl Strategy<Value = Vec<Dimension>> {
+        prop::collection::vec(
+            (1..=4usize, 1..=4usize).prop_map(|(width, height)| Dimension { width, height }),
+            0..40,
+        )
+    }
+
+    proptest! {
+        /// the legacy visitor can swallow contents inside a rectangle of
+        /// empty cells, so holes are filled with 1x1 placeholders before
+        /// it runs and the placeholders are dropped from its output
+        #[test]
+        fn visitor_matches_legacy_first_fit(width in 4..=12usize, data in sizes()) {
+            let mut grid = Grid::new(width);
+            grid.add_all(data);
+
+            let mut filled = grid.clone();
+            let placeholder = usize::MAX - filled.contents.len();
+            filled
+                .contents
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, e)| e.is_none())
+                .for_each(|(idx, e)| *e = Some(placeholder + idx));
+            let legacy: Vec<_> = legacy_visit(&filled)
+                .into_iter()
+                .filter(|c| c.data < placeholder)
+                .collect();
+            prop_assert_eq!(summary(&grid.into_iter().collect::<Vec<_>>()), summary(&legacy));
+        }
+
+        /// the legacy visitor marks rectangles of empty cells as seen and can
+        /// swallow contents inside them, so layouts with holes are checked by
+        /// rebuilding the grid from the visited contents instead
+        #[test]
+        fn visitor_reconstructs_layouts_with_holes(
+            width in 4..=12usize,
+            data in sizes(),
+            best_fit in any::<bool>(),
+        ) {
+            let mut grid = Grid::new(width);
+            match best_fit {
+                true => grid.add_all_with(&BestFit, data.iter().copied()),
+                false => grid.add_all_with(&Skyline, data.iter().copied()),
+            }
+
+            let mut rebuilt = Grid::new(width);
+            let contents: Vec<_> = grid.clone().into_iter().collect();
+            prop_assert_eq!(contents.len(), data.len());
+            contents.iter().for_each(|c| {
+                let idx = rebuilt.to_index(c.origin);
+                rebuilt.insert_at(idx, c.data, &c.size).unwrap();
+            });
+            if let Some(last) = grid.contents.len().checked_sub(1) {
+                rebuilt.extend_to(last);
+            }
+            prop_assert_eq!(rebuilt.contents, grid.contents);
+        }
+    }
+
+    #[test]
+    fn it_should_yield_inserted_rectangles() {
+        let unit = Dimension {
+            width: 1,
+            height: 1,
+        };
+        let mut grid = Grid::new(3);
+        grid.insert_at(4, 1, &unit).unwrap();
+        grid.insert_at(1, 9, &unit).unwrap();
+        grid.insert_at(
+            3,
+            7,
+            &Dimension {
+                width: 1,
+                height: 2,
+            },
+        )
+        .unwrap();
+        // placing an id again moves it rather than adding a second rectangle
+        grid.insert_at(0, 1, &unit).unwrap();
+
+        assert_matches!(
+            grid.as_slice(),
+            [
+                Some(1),
+                Some(9),
+                None,
+                Some(7),
+                None,
+                None,
+                Some(7),
+                None,
+                None
+            ]
+        );
+        let out: Vec<_> = grid
+            .into_iter()
+            .map(|c| (c.data, c.origin, (c.size.width, c.size.height)))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                (1, Coord { x: 0, y: 0 }, (1, 1)),
+                (9, Coord { x: 1, y: 0 }, (1, 1)),
+                (7, Coord { x: 0, y: 1 }, (1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_remove_and_compact() {
+        let data =
+            [(2, 1), (2, 1), (1, 2), (1, 2)].map(|(width, height)| Dimension { width, height });
+        let mut grid = Grid::new(4);
+        grid.add_all(data);
+
+        assert_matches!(grid.remove(&0), Some(GridContent { data: 0, .. }));
+        assert_matches!(grid.remove(&0), None);
+        assert_eq!(grid.to_string(), ". . 1 1\n2 3 . .\n2 3 . .\n");
+
+        grid.compact();
+        assert_eq!(grid.to_string(), "2 3 1 1\n2 3 . .\n");
+        assert_eq!(grid.height(), 2);
+    }
+
+    #[test]
+    fn it_should_insert_into_freed_space() {
+        let data = [(2, 1), (2, 1)].map(|(width, height)| Dimension { width, height });
+        let mut grid = Grid::new(4);
+        grid.add_all(data);
+        grid.remove(&0);
+
+        grid.insert(
+            2,
+            &Dimension {
+                width: 1,
+                height: 1,
+            },
+        );
+        grid.insert(
+            3,
+            &Dimension {
+                width: 3,
+                height: 1,
+            },
+        );
+        assert_eq!(grid.to_string(), "2 . 1 1\n3 3 3 .\n");
+
+        assert_eq!(
+            grid.try_insert(
+                4,
+                &Dimension {
+                    width: 5,
+                    height: 1,
+                }
+            ),
+            Err(GridError::TooWide {
+                width: 5,
+                columns: 4
+            })
+        );
+        assert_eq!(
+            grid.try_insert(
+                4,
+                &Dimension {
+                    width: 0,
+                    height: 0,
+                }
+            ),
+            Err(GridError::EmptySize {
+                width: 0,
+                height: 0
+            })
+        );
+        assert_eq!(grid.to_string(), "2 . 1 1\n3 3 3 .\n");
+    }
+
+    #[test]
+    fn it_should_place_only_on_empty_cells() {
+        let unit = Dimension {
+            width: 1,
+            height: 1,
+        };
+        let mut grid = Grid::new(3);
+        assert!(grid.place(0, Coord { x: 1, y: 1 }, &unit));
+        assert!(!grid.place(
+            1,
+            Coord { x: 0, y: 1 },
+            &Dimension {
+                width: 2,
+                height: 1
+            }
+        ));
+        assert!(!grid.place(
+            1,
+            Coord { x: 2, y: 0 },
+            &Dimension {
+                width: 2,
+                height: 1
+            }
+        ));
+        assert_eq!(grid.to_string(), ". . .\n. 0 .\n");
+    }
+
+    #[test]
+    fn it_should_pack_around_pinned_items() {
+        let hero = GridContent::new(
+            9,
+            Coord { x: 1, y: 0 },
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+        );
+        let overlapping = GridContent::new(
+            8,
+            Coord { x: 2, y: 1 },
+            Dimension {
+                width: 1,
+                height: 1,
+            },
+        );
+        let mut grid = Grid::new(4);
+        let skipped = grid.pin([hero, overlapping]);
+        assert_matches!(skipped, [GridContent { data: 8, .. }]);
+
+        let data =
+            [(1, 1), (2, 1), (1, 1), (1, 1)].map(|(width, height)| Dimension { width, height });
+        grid.add_all(data);
+        assert_eq!(grid.to_string(), "0 9 9 2\n3 9 9 .\n1 1 . .\n");
+    }
+
+    #[test]
+    fn it_should_pin_what_it_yields() {
+        let data = [(2, 1), (1, 2), (1, 1), (3, 2), (1, 1)]
+            .map(|(width, height)| Dimension { width, height });
+        let flows = [
+            Flow::default(),
+            Flow::right_to_left(),
+            Flow::columns(),
+            Flow {
+                direction: flow::Direction::RightToLeft,
+                ..Flow::columns()
+            },
+        ];
+        flows.into_iter().for_each(|flow| {
+            let mut grid = Grid::new_with_flow(3, flow);
+            grid.add_all(data);
+
+            let mut pinned = Grid::new_with_flow(3, flow);
+            // right to left columns are measured from the left edge of the
+            // whole grid, so it needs all its columns before pinning
+            if flow.axis == flow::Axis::Columns && flow.direction == flow::Direction::RightToLeft {
+                pinned.extend_to(grid.contents.len() - 1);
+            }
+            let skipped = pinned.pin(grid.clone());
+            assert!(skipped.is_empty(), "{flow:?} {skipped:?}");
+
+            assert_eq!(pinned.to_string(), grid.to_string(), "{flow:?}");
+        })
+    }
+
+    #[test]
+    fn it_should_fill_columns() {
+        let data =
+            [(1, 1), (2, 1), (1, 2), (1, 1)].map(|(width, height)| Dimension { width, height });
+        let mut grid = Grid::new_with_flow(2, Flow::columns());
+        grid.add_all(data);
+
+        assert_eq!(grid.to_string(), "0 3 2\n1 1 2\n");
+        let out: Vec<_> = grid
+            .into_iter()
+            .map(|c| (c.data, c.origin, (c.size.width, c.size.height)))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                (0, Coord { x: 0, y: 0 }, (1, 1)),
+                (1, Coord { x: 0, y: 1 }, (2, 1)),
+                (3, Coord { x: 1, y: 0 }, (1, 1)),
+                (2, Coord { x: 2, y: 0 }, (1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_fill_right_to_left() {
+        let data = [(2, 1), (1, 2), (1, 1)].map(|(width, height)| Dimension { width, height });
+        let mut grid = Grid::new_with_flow(3, Flow::right_to_left());
+        grid.add_all(data);
+        assert!(grid.place(
+            3,
+            Coord { x: 1, y: 2 },
+            &Dimension {
+                width: 2,
+                height: 1
+            }
+        ));
+        // the cell left of the gap is taken by the second photo
+        assert!(!grid.place(
+            4,
+            Coord { x: 0, y: 1 },
+            &Dimension {
+                width: 2,
+                height: 1
+            }
+        ));
+
+        assert_eq!(grid.to_string(), "1 0 0\n1 . 2\n. 3 3\n");
+        let origins: Vec<_> = grid.into_iter().map(|c| (c.data, c.origin)).collect();
+        assert_eq!(
+            origins,
+            [
+                (0, Coord { x: 1, y: 0 }),
+                (1, Coord { x: 0, y: 0 }),
+                (2, Coord { x: 2, y: 1 }),
+                (3, Coord { x: 1, y: 2 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn visitor_should_handle_large_sparse_grids() {
+        let mut grid = Grid::new(4);
+        grid.add_all([Dimension {
+            width: 1,
+            height: 1,
+        }]);
+        grid.extend_to(400_000 - 1);
+        grid.insert_at(
+            399_999,
+            1,
+            &Dimension {
+                width: 1,
+                height: 1,
+            },
+        )
+        .unwrap();
+
+        let out: Vec<_> = grid.into_iter().map(|c| (c.data, c.origin)).collect();
+        assert_eq!(
+            out,
+            [(0, Coord { x: 0, y: 0 }), (1, Coord { x: 3, y: 99_999 })]
+        );
+    }
 }
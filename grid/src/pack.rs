@@ -0,0 +1,374 @@
+use crate::{interval::IntervalGrid, Coord, Dimension, Grid, GridError, Size};
+
+/// an algorithm for placing items into a grid
+pub trait PackingStrategy {
+    /// place every item in the grid. each item is identified
+    /// in the grid by its index in `items`
+    fn pack<T>(&self, grid: &mut Grid<Option<usize>>, items: &[T])
+    where
+        T: Size;
+}
+
+/// place each item at the first index it fits, in input order
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FirstFit;
+
+impl PackingStrategy for FirstFit {
+    fn pack<T>(&self, grid: &mut Grid<Option<usize>>, items: &[T])
+    where
+        T: Size,
+    {
+        items
+            .iter()
+            .enumerate()
+            .for_each(|(id, el)| grid.insert_first_fit(id, el))
+    }
+}
+
+/// first fit which tracks the free columns of each row as intervals.
+/// rows too full for an item of some width are never searched again for
+/// that width, so large photo sets pack faster than with [`FirstFit`]
+/// while producing the same layout
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IntervalFirstFit;
+
+impl PackingStrategy for IntervalFirstFit {
+    fn pack<T>(&self, grid: &mut Grid<Option<usize>>, items: &[T])
+    where
+        T: Size,
+    {
+        let mut intervals = IntervalGrid::from_grid(grid);
+        items.iter().enumerate().for_each(|(id, el)| {
+            let origin = intervals.insert(Dimension {
+                width: el.width(),
+                height: el.height(),
+            });
+            let idx = grid.to_index(origin);
+            grid.insert_at(idx, id, el)
+                .expect("failed to insert at valid location");
+        })
+    }
+}
+
+/// first fit which may reorder items within a bounded window.
+///
+/// before each placement every ordering of the next `window` items
+/// is tried against a copy of the grid and the item which leads the
+/// ordering leaving the fewest empty cells is placed. ties keep the
+/// input order so the same input always produces the same layout
+#[derive(Debug, Clone, Copy)]
+pub struct Lookahead {
+    /// number of pending items considered for each placement
+    window: usize,
+}
+
+impl Lookahead {
+    /// the largest window accepted by [`Lookahead::new`]. every placement
+    /// copies the grid once per ordering of the window, so the cost grows
+    /// with the factorial of the window
+    pub const MAX_WINDOW: usize = 5;
+
+    pub fn new(window: usize) -> Result<Self, GridError> {
+        match (1..=Self::MAX_WINDOW).contains(&window) {
+            true => Ok(Self { window }),
+            false => Err(GridError::InvalidWindow {
+                window,
+                max: Self::MAX_WINDOW,
+            }),
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+impl Default for Lookahead {
+    fn default() -> Self {
+        Self { window: 3 }
+    }
+}
+
+impl PackingStrategy for Lookahead {
+    fn pack<T>(&self, grid: &mut Grid<Option<usize>>, items: &[T])
+    where
+        T: Size,
+    {
+        let mut pending: Vec<usize> = (0..items.len()).collect();
+        // how many times the first pending item has been passed over.
+        // forcing it after `window` skips stops an awkward item from
+        // drifting to the end of the layout
+        let mut skipped = 0;
+
+        while !pending.is_empty() {
+            let window = &pending[..self.window.clamp(1, pending.len())];
+
+            let next = match skipped >= self.window {
+                true => 0,
+                false => permutations(window.len())
+                    .into_iter()
+                    .min_by_key(|order| {
+                        let mut trial = grid.clone();
+                        order
+                            .iter()
+                            .map(|pos| window[*pos])
+                            .for_each(|id| trial.insert_first_fit(id, &items[id]));
+                        trial.empty_cells()
+                    })
+                    .and_then(|order| order.first().copied())
+                    .unwrap_or_default(),
+            };
+
+            skipped = match next {
+                0 => 0,
+                _ => skipped + 1,
+            };
+            let id = pending.remove(next);
+            grid.insert_first_fit(id, &items[id]);
+        }
+    }
+}
+
+/// place each item, in input order, at the position which grows the grid
+/// the least and then creates the fewest holes. a hole is an empty cell
+/// above the lowest occupied cell of its column, so filling an existing
+/// hole is preferred over sitting flush on the items above, which is
+/// preferred over leaving a gap. ties go to the earliest position
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BestFit;
+
+impl PackingStrategy for BestFit {
+    fn pack<T>(&self, grid: &mut Grid<Option<usize>>, items: &[T])
+    where
+        T: Size,
+    {
+        items.iter().enumerate().for_each(|(id, el)| {
+            let skyline = skyline(grid);
+            // appending a new row always fits so there is always a candidate
+            let end = grid.contents.len();
+            let best = grid
+                .available()
+                .chain(std::iter::once(end))
+                .filter(|idx| grid.does_fit_at(*idx, el))
+                .min_by_key(|idx| {
+                    let origin = grid.to_dimension(*idx);
+                    let growth = (origin.y + el.height()).saturating_sub(grid.height);
+                    (growth, new_holes(&skyline, origin, el))
+                })
+                .unwrap_or(end);
+            grid.insert_at(best, id, el)
+                .expect("failed to insert at valid location");
+        })
+    }
+}
+
+/// the first row below every occupied cell in each column
+fn skyline(grid: &Grid<Option<usize>>) -> Vec<usize> {
+    let mut skyline = vec![0; grid.width];
+    grid.contents
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.is_some())
+        .for_each(|(idx, _)| {
+            let Coord { x, y } = grid.to_dimension(idx);
+            skyline[x] = skyline[x].max(y + 1);
+        });
+    skyline
+}
+
+/// change in the number of holes from placing an item with `dimension` at
+/// `origin`. negative when the item fills holes which already existed
+fn new_holes(skyline: &[usize], origin: Coord<usize>, dimension: &impl Size) -> isize {
+    let bottom = origin.y + dimension.height();
+    skyline
+        .iter()
+        .skip(origin.x)
+        .take(dimension.width())
+        .map(|top| (bottom.max(*top) - top) as isize - dimension.height() as isize)
+        .sum()
+}
+
+/// place each item, in input order, on the lowest stretch of the
+/// skyline formed by the bottom edges of the items already placed.
+/// gaps under the skyline are never revisited, so rows read in
+/// input order like shelves. ties go to the leftmost position.
+/// cells occupied before packing, such as pinned items, start the
+/// skyline so nothing is placed above them in their columns
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Skyline;
+
+impl PackingStrategy for Skyline {
+    fn pack<T>(&self, grid: &mut Grid<Option<usize>>, items: &[T])
+    where
+        T: Size,
+    {
+        let width = grid.width;
+        let mut skyline = skyline(grid);
+
+        items.iter().enumerate().for_each(|(id, el)| {
+            let top = |x: usize| skyline[x..x + el.width()].iter().copied().max();
+            let (x, y) = (0..width)
+                .filter(|x| x + el.width() <= width)
+                .filter_map(|x| Some((x, top(x)?)))
+                .min_by_key(|(_, y)| *y)
+                // items wider than the grid start a new row on their own
+                .unwrap_or((0, skyline.iter().copied().max().unwrap_or_default()));
+
+            let idx = grid.to_index(Coord { x, y });
+            grid.insert_at(idx, id, el)
+                .expect("failed to insert at valid location");
+            skyline
+                .iter_mut()
+                .skip(x)
+                .take(el.width())
+                .for_each(|e| *e = y + el.height());
+        })
+    }
+}
+
+/// every ordering of `0..n` in lexicographic order
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    (0..n)
+        .flat_map(|first| {
+            permutations(n - 1).into_iter().map(move |rest| {
+                std::iter::once(first)
+                    .chain(rest.into_iter().map(|x| if x >= first { x + 1 } else { x }))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn permutations_are_lexicographic() {
+        assert_eq!(
+            permutations(3),
+            [
+                [0, 1, 2],
+                [0, 2, 1],
+                [1, 0, 2],
+                [1, 2, 0],
+                [2, 0, 1],
+                [2, 1, 0]
+            ]
+        );
+    }
+
+    #[test]
+    fn lookahead_of_1_is_first_fit() {
+        let data = [(2, 1), (1, 2), (1, 1), (2, 1), (1, 1), (3, 1)]
+            .map(|(width, height)| Dimension { width, height });
+
+        let mut first_fit = Grid::new(3);
+        first_fit.add_all(data);
+        let mut lookahead = Grid::new(3);
+        lookahead.add_all_with(&Lookahead::new(1).unwrap(), data);
+
+        assert_eq!(first_fit.contents, lookahead.contents);
+    }
+
+    #[test]
+    fn lookahead_bounds_the_window() {
+        assert_matches!(
+            Lookahead::new(0),
+            Err(GridError::InvalidWindow { window: 0, max: 5 })
+        );
+        assert_matches!(
+            Lookahead::new(6),
+            Err(GridError::InvalidWindow { window: 6, max: 5 })
+        );
+        assert_matches!(Lookahead::new(Lookahead::MAX_WINDOW), Ok(l) => {
+            assert_eq!(l.window(), 5)
+        });
+    }
+
+    #[test]
+    fn lookahead_fills_gaps_left_by_first_fit() {
+        let data = [(1, 1), (1, 2), (2, 3)].map(|(width, height)| Dimension { width, height });
+
+        let mut first_fit = Grid::new(3);
+        first_fit.add_all(data);
+        assert_eq!(first_fit.empty_cells(), 6);
+
+        let mut lookahead = Grid::new(3);
+        lookahead.add_all_with(&Lookahead::default(), data);
+
+        assert_matches!(
+            lookahead.contents,
+            [
+                Some(0),
+                Some(2),
+                Some(2),
+                Some(1),
+                Some(2),
+                Some(2),
+                Some(1),
+                Some(2),
+                Some(2)
+            ]
+        )
+    }
+
+    #[test]
+    fn best_fit_fills_holes() {
+        let data =
+            [(1, 1), (2, 2), (2, 2), (1, 1)].map(|(width, height)| Dimension { width, height });
+
+        let mut grid = Grid::new(4);
+        grid.add_all_with(&BestFit, data);
+
+        assert_matches!(
+            grid.contents,
+            [
+                Some(0),
+                Some(1),
+                Some(1),
+                None,
+                Some(3),
+                Some(1),
+                Some(1),
+                None,
+                Some(2),
+                Some(2),
+                None,
+                None,
+                Some(2),
+                Some(2),
+                None,
+                None
+            ]
+        )
+    }
+
+    #[test]
+    fn skyline_does_not_revisit_gaps() {
+        let data =
+            [(1, 2), (1, 1), (2, 1), (1, 1)].map(|(width, height)| Dimension { width, height });
+
+        let mut grid = Grid::new(3);
+        grid.add_all_with(&Skyline, data);
+
+        assert_matches!(
+            grid.contents,
+            [
+                Some(0),
+                Some(1),
+                None,
+                Some(0),
+                Some(2),
+                Some(2),
+                Some(3),
+                None,
+                None
+            ]
+        )
+    }
+}
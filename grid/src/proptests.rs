@@ -0,0 +1,134 @@
+use proptest::prelude::*;
+
+use crate::{
+    flow::{Direction, Flow},
+    pack::{BestFit, FirstFit, IntervalFirstFit, Lookahead, Skyline},
+    ClampConfig, ClampWidthTo, Coord, Dimension, Grid, GridContent, Size,
+};
+
+/// a grid extent and sizes which are up to twice as wide as it before
+/// clamping. heights stay within the extent so columns can hold them too
+fn layout() -> impl Strategy<Value = (usize, Vec<Dimension>)> {
+    (1..=12usize).prop_flat_map(|extent| {
+        let size = (1..=extent * 2, 1..=extent.min(6)).prop_map(move |(width, height)| {
+            Dimension { width, height }.clamp_width_to(ClampConfig {
+                min_width: None,
+                max_width: Some(extent),
+            })
+        });
+        (Just(extent), prop::collection::vec(size, 0..60))
+    })
+}
+
+fn flow() -> impl Strategy<Value = Flow> {
+    prop_oneof![
+        Just(Flow::default()),
+        Just(Flow::right_to_left()),
+        Just(Flow::columns()),
+        Just(Flow {
+            direction: Direction::RightToLeft,
+            ..Flow::columns()
+        }),
+    ]
+}
+
+fn pack(grid: &mut Grid<Option<usize>>, strategy: usize, data: &[Dimension]) {
+    let data = data.iter().copied();
+    match strategy {
+        0 => grid.add_all_with(&FirstFit, data),
+        1 => grid.add_all_with(&Lookahead::default(), data),
+        2 => grid.add_all_with(&BestFit, data),
+        3 => grid.add_all_with(&Skyline, data),
+        _ => grid.add_all_with(&IntervalFirstFit, data),
+    }
+}
+
+/// the screen size of the area covered by the contents
+fn extent(contents: &[GridContent<usize>]) -> Dimension {
+    contents.iter().fold(
+        Dimension {
+            width: 0,
+            height: 0,
+        },
+        |acc, c| Dimension {
+            width: acc.width.max(c.origin.x + c.size.width),
+            height: acc.height.max(c.origin.y + c.size.height),
+        },
+    )
+}
+
+proptest! {
+    #[test]
+    fn packing_invariants(
+        (extent_cells, data) in layout(),
+        flow in flow(),
+        strategy in 0..5usize,
+    ) {
+        let mut grid = Grid::new_with_flow(extent_cells, flow);
+        pack(&mut grid, strategy, &data);
+        let contents: Vec<_> = grid.clone().into_iter().collect();
+
+        // every item placed exactly once
+        let mut ids: Vec<usize> = contents.iter().map(|c| *c.content()).collect();
+        ids.sort();
+        prop_assert_eq!(ids, (0..data.len()).collect::<Vec<_>>());
+
+        // each item keeps the size it asked for
+        for c in &contents {
+            let requested = data[*c.content()];
+            prop_assert_eq!(
+                (c.size.width, c.size.height),
+                (requested.width, requested.height)
+            );
+        }
+
+        // nothing past the fixed extent of the grid
+        let screen = extent(&contents);
+        match flow.axis {
+            crate::flow::Axis::Rows => prop_assert!(screen.width <= extent_cells),
+            crate::flow::Axis::Columns => prop_assert!(screen.height <= extent_cells),
+        }
+
+        // no overlapping cells
+        let mut covered = vec![false; screen.width * screen.height];
+        for c in &contents {
+            for Coord { x, y } in c.size.coords_iter() {
+                let cell = (c.origin.y + y) * screen.width + c.origin.x + x;
+                prop_assert!(!covered[cell], "{:?} overlaps another item", c);
+                covered[cell] = true;
+            }
+        }
+    }
+
+    #[test]
+    fn visitor_round_trips(
+        (extent_cells, data) in layout(),
+        flow in flow(),
+        strategy in 0..5usize,
+    ) {
+        let mut grid = Grid::new_with_flow(extent_cells, flow);
+        pack(&mut grid, strategy, &data);
+        let contents: Vec<_> = grid.clone().into_iter().collect();
+
+        // place every visited item back where it was yielded. right to left
+        // columns are measured from the left edge, so start at full size
+        let mut rebuilt = Grid::new_with_flow(extent_cells, flow);
+        if let Some(last) = grid.contents.len().checked_sub(1) {
+            rebuilt.extend_to(last);
+        }
+        for c in &contents {
+            prop_assert!(rebuilt.place(*c.content(), c.origin, &c.size), "{:?} does not fit", c);
+        }
+
+        prop_assert_eq!(rebuilt.to_string(), grid.to_string());
+        let visited: Vec<_> = rebuilt
+            .into_iter()
+            .map(|c| (*c.content(), c.origin, c.size.width, c.size.height))
+            .collect();
+        let expected: Vec<_> = contents
+            .iter()
+            .map(|c| (*c.content(), c.origin, c.size.width, c.size.height))
+            .collect();
+        prop_assert_eq!(visited, expected);
+    }
+}
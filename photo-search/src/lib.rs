@@ -2,7 +2,7 @@ use chrono::{DateTime, FixedOffset, Utc};
 use photogrid::PhotoLayoutData;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct SearchFilter {
     pub before: Option<u64>,
     pub after: Option<u64>,
@@ -24,10 +24,7 @@ impl PhotoAccess for PhotoLayoutData {
     }
 
     fn get_rating(&self) -> u8 {
-        self.metadata
-            .get("rating")
-            .and_then(|x| x.parse().ok())
-            .unwrap_or_default()
+        self.rating()
     }
 }
 
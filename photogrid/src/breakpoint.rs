@@ -0,0 +1,97 @@
+use grid::ClampConfig;
+use serde::{Deserialize, Serialize};
+
+/// a range of viewport widths and the grid laid out for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    /// the narrowest viewport in px the grid is shown on
+    pub min_px: u32,
+    /// the viewport in px the next breakpoint takes over from, none for the widest
+    pub max_px: Option<u32>,
+    /// the number of columns in the grid
+    pub columns: usize,
+    /// the widths photos are clamped to on this grid
+    pub clamp: ClampConfig,
+}
+
+/// the breakpoints of [`crate::ResponsivePhotoGrid::from_layout_data`]. the
+/// narrowest grid stretches every photo across the screen
+pub const DEFAULT_BREAKPOINTS: [Breakpoint; 5] = [
+    // photos are exactly as wide as the grid, so panoramas which round
+    // wider than 3 columns are shrunk rather than running off its edge
+    Breakpoint {
+        min_px: 0,
+        max_px: Some(640),
+        columns: 3,
+        clamp: ClampConfig {
+            min_width: Some(3),
+            max_width: Some(3),
+        },
+    },
+    Breakpoint::new(640, Some(768), 4),
+    Breakpoint::new(768, Some(1024), 6),
+    Breakpoint::new(1024, Some(1536), 8),
+    Breakpoint::new(1536, None, 12),
+];
+
+impl Breakpoint {
+    /// a breakpoint whose photos are at most as wide as the grid
+    pub const fn new(min_px: u32, max_px: Option<u32>, columns: usize) -> Self {
+        Self {
+            min_px,
+            max_px,
+            columns,
+            clamp: ClampConfig {
+                min_width: None,
+                max_width: Some(columns),
+            },
+        }
+    }
+
+    /// the media query matching the viewports of this breakpoint, none when
+    /// it matches every viewport. the upper bound stops just short of
+    /// `max_px` so fractional viewport widths still match one breakpoint
+    pub fn media_query(&self) -> Option<String> {
+        match (self.min_px, self.max_px) {
+            (0, None) => None,
+            (0, Some(max)) => Some(format!("(max-width: {}.98px)", max - 1)),
+            (min, None) => Some(format!("(min-width: {min}px)")),
+            (min, Some(max)) => Some(format!(
+                "(min-width: {min}px) and (max-width: {}.98px)",
+                max - 1
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_breakpoints_should_be_contiguous() {
+        assert_eq!(DEFAULT_BREAKPOINTS[0].min_px, 0);
+        assert_eq!(DEFAULT_BREAKPOINTS.last().unwrap().max_px, None);
+        for pair in DEFAULT_BREAKPOINTS.windows(2) {
+            assert_eq!(pair[0].max_px, Some(pair[1].min_px));
+        }
+    }
+
+    #[test]
+    fn it_should_build_media_queries() {
+        let queries: Vec<_> = DEFAULT_BREAKPOINTS
+            .iter()
+            .map(Breakpoint::media_query)
+            .collect();
+        assert_eq!(
+            queries,
+            [
+                Some("(max-width: 639.98px)".to_string()),
+                Some("(min-width: 640px) and (max-width: 767.98px)".to_string()),
+                Some("(min-width: 768px) and (max-width: 1023.98px)".to_string()),
+                Some("(min-width: 1024px) and (max-width: 1535.98px)".to_string()),
+                Some("(min-width: 1536px)".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    hash::Hash,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use lru::LruCache;
+
+use crate::{PhotoLayoutData, ResponsivePhotoGrid};
+
+/// how well a [`LayoutCache`] has been doing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// the number of layouts held
+    pub len: usize,
+    pub capacity: usize,
+}
+
+/// laid out grids keyed by whatever picked and ordered their photos, dropping
+/// the least recently used layout once `capacity` are held. `V` may hold
+/// anything derived from the layout along with it
+pub struct LayoutCache<K, V = ResponsivePhotoGrid<PhotoLayoutData>>
+where
+    K: Hash + Eq,
+{
+    inner: Mutex<Inner<K, V>>,
+}
+
+struct Inner<K, V>
+where
+    K: Hash + Eq,
+{
+    /// a layout is built into its cell outside the lock, so a key being
+    /// built can be waited on without blocking the others
+    layouts: LruCache<K, Arc<OnceLock<Arc<V>>>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K, V> LayoutCache<K, V>
+where
+    K: Hash + Eq,
+{
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                layouts: LruCache::new(capacity),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// the layout stored for `key`, running `build` only if there is none.
+    /// the lock is not held while building so a slow layout doesn't block
+    /// requests for other keys, requests for the same key wait for the
+    /// first build instead of running their own
+    pub fn get_or_insert_with(&self, key: K, build: impl FnOnce() -> V) -> Arc<V> {
+        let cell = {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            match inner.layouts.get(&key).cloned() {
+                Some(cell) => {
+                    inner.hits += 1;
+                    cell
+                }
+                None => {
+                    inner.misses += 1;
+                    let cell = Arc::new(OnceLock::new());
+                    inner.layouts.put(key, cell.clone());
+                    cell
+                }
+            }
+        };
+
+        cell.get_or_init(|| Arc::new(build())).clone()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            len: inner.layouts.len(),
+            capacity: inner.layouts.cap().get(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Barrier,
+        },
+        thread,
+    };
+
+    use grid::pack::FirstFit;
+
+    use super::*;
+
+    fn layout(count: usize) -> ResponsivePhotoGrid<PhotoLayoutData> {
+        let mut photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        photos.truncate(count);
+        ResponsivePhotoGrid::from_layout_data(photos, &FirstFit)
+    }
+
+    #[test]
+    fn it_should_only_build_on_a_miss() {
+        let cache = LayoutCache::new(NonZeroUsize::new(2).unwrap());
+        let first = cache.get_or_insert_with(1, || layout(3));
+        let again = cache.get_or_insert_with(1, || panic!("the layout is cached"));
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                len: 1,
+                capacity: 2
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_build_concurrent_misses_once() {
+        let cache = LayoutCache::new(NonZeroUsize::new(2).unwrap());
+        let builds = AtomicUsize::new(0);
+        let barrier = Barrier::new(4);
+
+        let layouts: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(|| {
+                        barrier.wait();
+                        cache.get_or_insert_with(1, || {
+                            builds.fetch_add(1, Ordering::SeqCst);
+                            layout(3)
+                        })
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+        assert!(layouts.iter().all(|x| Arc::ptr_eq(x, &layouts[0])));
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn it_should_evict_the_least_recently_used() {
+        let cache = LayoutCache::new(NonZeroUsize::new(2).unwrap());
+        cache.get_or_insert_with(1, || layout(1));
+        cache.get_or_insert_with(2, || layout(2));
+        cache.get_or_insert_with(1, || panic!("the layout is cached"));
+        cache.get_or_insert_with(3, || layout(3));
+
+        let rebuilt = cache.get_or_insert_with(2, || layout(4));
+        assert_eq!(rebuilt.contents_len(), 4);
+        assert_eq!(cache.stats().len, 2);
+        assert_eq!(cache.stats().misses, 4);
+    }
+}
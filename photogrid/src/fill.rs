@@ -0,0 +1,184 @@
+use grid::{aspect_error, Dimension, GridContent, Size};
+
+use crate::PhotoGrid;
+
+/// configuration for [`PhotoGrid::fill_holes`]
+#[derive(Debug, Clone, Copy)]
+pub struct FillConfig {
+    /// how far a photo's aspect ratio may drift from the size it was
+    /// packed at, as a fraction. `0.5` lets a 3:2 photo grow to 3:3
+    /// but not to 3:4
+    pub max_distortion: f64,
+}
+
+impl Default for FillConfig {
+    fn default() -> Self {
+        Self {
+            max_distortion: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Grow {
+    Right,
+    Left,
+    Down,
+    Up,
+}
+
+/// the content grown by one cell in the direction
+fn grown<T>(content: &GridContent<T>, direction: Grow) -> Option<GridContent<T>>
+where
+    T: Copy,
+{
+    let mut out = *content;
+    match direction {
+        Grow::Right => out.size.width += 1,
+        Grow::Down => out.size.height += 1,
+        Grow::Left => {
+            out.origin.x = out.origin.x.checked_sub(1)?;
+            out.size.width += 1;
+        }
+        Grow::Up => {
+            out.origin.y = out.origin.y.checked_sub(1)?;
+            out.size.height += 1;
+        }
+    }
+    Some(out)
+}
+
+impl<T> PhotoGrid<T>
+where
+    T: Copy,
+{
+    /// grow photos next to each hole left by the packer until the holes
+    /// are filled or no neighbour can grow without exceeding the
+    /// distortion budget. the height of the grid never changes
+    pub fn fill_holes(mut self, config: FillConfig) -> Self {
+        let packed: Vec<Dimension> = self.grid.iter().map(|c| c.size).collect();
+        let height = self.height();
+
+        while let Some((idx, grown)) = self.next_growth(&packed, height, config) {
+            self.grid[idx] = grown;
+        }
+        self
+    }
+
+    /// find the first hole which a neighbouring photo can grow into
+    fn next_growth(
+        &self,
+        packed: &[Dimension],
+        height: usize,
+        config: FillConfig,
+    ) -> Option<(usize, GridContent<T>)> {
+        let occupancy = self.occupancy();
+        let cell = |x: usize, y: usize| match x < self.width && y < height {
+            true => occupancy.get(y * self.width + x).copied(),
+            false => None,
+        };
+
+        occupancy
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.is_none())
+            .find_map(|(hole, _)| {
+                let (x, y) = (hole % self.width, hole / self.width);
+                let neighbours = [
+                    (x.checked_sub(1).map(|x| (x, y)), Grow::Right),
+                    (Some((x + 1, y)), Grow::Left),
+                    (y.checked_sub(1).map(|y| (x, y)), Grow::Down),
+                    (Some((x, y + 1)), Grow::Up),
+                ];
+
+                neighbours
+                    .into_iter()
+                    .filter_map(|(coord, direction)| {
+                        let (x, y) = coord?;
+                        Some((cell(x, y)??, direction))
+                    })
+                    .find_map(|(idx, direction)| {
+                        let grown = grown(&self.grid[idx], direction)?;
+                        let fits = grown.origin.x + grown.size.width <= self.width
+                            && grown.origin.y + grown.size.height <= height
+                            && grown.size.coords_iter().all(|c| {
+                                cell(grown.origin.x + c.x, grown.origin.y + c.y)
+                                    .is_some_and(|e| e.is_none_or(|e| e == idx))
+                            });
+                        let within_budget =
+                            aspect_error(&grown.size, &packed[idx]) <= config.max_distortion;
+                        (fits && within_budget).then_some((idx, grown))
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use grid::{pack::FirstFit, Coord};
+
+    fn layout() -> PhotoGrid<Dimension> {
+        let data = [
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+            Dimension {
+                width: 1,
+                height: 1,
+            },
+        ];
+        PhotoGrid::new_with_mapper(&data, 3, &FirstFit, |x| *x)
+    }
+
+    #[test]
+    fn it_should_grow_into_holes() {
+        let g = layout().fill_holes(FillConfig {
+            max_distortion: 1.0,
+        });
+
+        assert_matches!(
+            g.grid,
+            [
+                GridContent {
+                    origin: Coord { x: 0, y: 0 },
+                    size: Dimension {
+                        width: 2,
+                        height: 2
+                    },
+                    ..
+                },
+                GridContent {
+                    origin: Coord { x: 2, y: 0 },
+                    size: Dimension {
+                        width: 1,
+                        height: 2
+                    },
+                    ..
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_respect_distortion_budget() {
+        let g = layout().fill_holes(FillConfig::default());
+
+        assert_matches!(
+            g.grid,
+            [
+                _,
+                GridContent {
+                    origin: Coord { x: 2, y: 0 },
+                    size: Dimension {
+                        width: 1,
+                        height: 1
+                    },
+                    ..
+                },
+            ]
+        )
+    }
+}
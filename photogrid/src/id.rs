@@ -0,0 +1,149 @@
+use std::{collections::HashMap, fmt, num::ParseIntError, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::PhotoLayoutData;
+
+/// the metadata holding the crc32 checksum of the original file
+pub const CHECKSUM_KEY: &str = "original-file-checksum-crc32";
+
+/// identifies a photo no matter which resized variant of it is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PhotoId(pub u32);
+
+impl fmt::Display for PhotoId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for PhotoId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+/// the ids separated by commas, as read by [`parse_ids`]
+pub fn join_ids(ids: &[PhotoId]) -> String {
+    ids.iter()
+        .map(PhotoId::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// the ids in a comma separated list, skipping any which are malformed
+pub fn parse_ids(s: &str) -> Vec<PhotoId> {
+    s.split(',')
+        .filter_map(|id| id.trim().parse().ok())
+        .collect()
+}
+
+/// the position of each photo in a slice of photos, looked up by its id
+#[derive(Debug, Clone, Default)]
+pub struct PhotoIndex(HashMap<PhotoId, usize>);
+
+impl PhotoIndex {
+    /// index every photo with an id. the first photo wins when ids repeat
+    pub fn new(photos: &[PhotoLayoutData]) -> Self {
+        let mut index = HashMap::with_capacity(photos.len());
+        photos.iter().enumerate().for_each(|(idx, photo)| {
+            if let Some(id) = photo.id() {
+                index.entry(id).or_insert(idx);
+            }
+        });
+        Self(index)
+    }
+
+    /// the position of the photo with `id`
+    pub fn get(&self, id: PhotoId) -> Option<usize> {
+        self.0.get(&id).copied()
+    }
+}
+
+/// the file name of a resized variant without its extension, which every
+/// variant of a photo shares
+fn source_key(url: &Url) -> Option<&str> {
+    let name = url.path_segments()?.next_back()?;
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    (!stem.is_empty()).then_some(stem)
+}
+
+impl PhotoLayoutData {
+    /// the checksum of the original file, or a checksum of the source key
+    /// for photos uploaded before the checksum was recorded
+    pub fn id(&self) -> Option<PhotoId> {
+        if let Some(checksum) = self.metadata.get(CHECKSUM_KEY) {
+            if let Ok(id) = checksum.parse() {
+                return Some(id);
+            }
+        }
+        let key = self.srcs.iter().find_map(|src| source_key(&src.url))?;
+        Some(PhotoId(crc32fast::hash(key.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photos() -> Vec<PhotoLayoutData> {
+        serde_json::from_str(include_str!("../../data.json")).unwrap()
+    }
+
+    #[test]
+    fn it_should_use_the_original_checksum() {
+        let photo = &photos()[0];
+        assert_eq!(photo.id(), Some(PhotoId(3365839178)));
+        assert_eq!("3365839178".parse(), Ok(PhotoId(3365839178)));
+        assert_eq!(PhotoId(3365839178).to_string(), "3365839178");
+    }
+
+    #[test]
+    fn it_should_round_trip_id_lists() {
+        let ids = [PhotoId(3), PhotoId(1), PhotoId(2)];
+        assert_eq!(join_ids(&ids), "3,1,2");
+        assert_eq!(parse_ids(&join_ids(&ids)), ids);
+        assert_eq!(parse_ids("4, x,,5"), [PhotoId(4), PhotoId(5)]);
+        assert_eq!(parse_ids(""), []);
+    }
+
+    #[test]
+    fn it_should_index_photos_by_id() {
+        let mut photos = photos();
+        photos.truncate(3);
+        photos.push(photos[1].clone());
+        let index = PhotoIndex::new(&photos);
+
+        photos.iter().take(3).enumerate().for_each(|(idx, photo)| {
+            assert_eq!(index.get(photo.id().unwrap()), Some(idx));
+        });
+        assert_eq!(index.get(photos[3].id().unwrap()), Some(1));
+        assert_eq!(index.get(PhotoId(0)), None);
+    }
+
+    #[test]
+    fn ids_should_be_unique() {
+        let mut ids: Vec<_> = photos().iter().map(|photo| photo.id().unwrap()).collect();
+        let len = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), len);
+    }
+
+    #[test]
+    fn it_should_fall_back_to_the_source_key() {
+        let mut photo = photos().swap_remove(0);
+        photo.metadata.remove(CHECKSUM_KEY);
+        let id = photo.id();
+        assert_eq!(id, Some(PhotoId(crc32fast::hash(b"_AYE7507"))));
+
+        // any single variant identifies the photo
+        photo.srcs.reverse();
+        photo.srcs.truncate(1);
+        assert_eq!(photo.id(), id);
+    }
+}
@@ -1,24 +1,140 @@
-use std::{collections::HashMap, ops::Not};
+use std::{collections::HashMap, num::NonZeroUsize, ops::Not};
 
 use grid::{
-    ClampConfig, ClampWidthTo, Dimension, FromSize, Grid, GridContent, Intersect,
-    RoundedAspectRatio, Size,
+    pack::PackingStrategy, ClampConfig, ClampWidthTo, Coord, Dimension, Emphasize, Grid,
+    GridContent, GridError, Intersect, Precision, Rounded, Size,
 };
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+mod breakpoint;
+mod cache;
+mod fill;
+mod id;
+mod metrics;
+mod snapshot;
+mod svg;
+
+pub use breakpoint::{Breakpoint, DEFAULT_BREAKPOINTS};
+pub use cache::{CacheStats, LayoutCache};
+pub use fill::FillConfig;
+pub use id::{join_ids, parse_ids, PhotoId, PhotoIndex, CHECKSUM_KEY};
+pub use metrics::LayoutMetrics;
+pub use snapshot::{BreakpointLayout, LayoutSnapshot, SnapshotItem, SNAPSHOT_VERSION};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SrcSet {
     pub dimensions: Dimension,
     pub url: Url,
 }
 
+impl SrcSet {
+    /// the full size jpeg this variant was resized from
+    pub fn original(&self) -> Option<Url> {
+        let name = self
+            .url
+            .path_segments()?
+            .next_back()?
+            .replace(".avif", ".jpg");
+        let mut out = self.url.clone();
+        out.path_segments_mut()
+            .ok()?
+            .clear()
+            .push("original")
+            .push(&name);
+        Some(out)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PhotoError {
+    #[error("photo has no srcset")]
+    NoSrcSet,
+    #[error("layout snapshot version {found} is not supported, expected {SNAPSHOT_VERSION}")]
+    UnsupportedSnapshot { found: u32 },
+    #[error("photo has no id")]
+    NoId,
+    #[error("layout snapshot places photo {id} which was not given")]
+    UnknownPhoto { id: PhotoId },
+    #[error("there is no photo {id}, the grid holds {len}")]
+    OutOfBounds { id: usize, len: usize },
+    #[error("photos {ids:?} no longer fit on a grid {columns} wide")]
+    DoesNotFit { ids: Vec<usize>, columns: usize },
+    #[error(transparent)]
+    Grid(#[from] GridError),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PhotoLayoutData {
     pub srcs: Vec<SrcSet>,
     pub metadata: HashMap<String, String>,
 }
 
+/// the metadata holding the star rating of a photo
+pub const RATING_KEY: &str = "rating";
+
+/// photos rated at least this highly are laid out at twice the size
+pub const EMPHASIS_RATING: u8 = 5;
+
+impl PhotoLayoutData {
+    /// the star rating of the photo, 0 when it is unrated
+    pub fn rating(&self) -> u8 {
+        self.metadata
+            .get(RATING_KEY)
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// the factor a photo's footprint is scaled by, from its rating
+    pub fn emphasis(&self) -> usize {
+        match self.rating() >= EMPHASIS_RATING {
+            true => 2,
+            false => 1,
+        }
+    }
+
+    /// the dimensions of the largest srcset
+    pub fn dimensions(&self) -> Option<Dimension> {
+        self.largest().map(|x| x.dimensions)
+    }
+
+    /// the widest srcset
+    pub fn largest(&self) -> Option<&SrcSet> {
+        self.srcs.iter().max_by_key(|x| x.dimensions.width)
+    }
+
+    /// the narrowest srcset at least `width` wide, or the widest if none are
+    pub fn src_at_least(&self, width: usize) -> Option<&SrcSet> {
+        self.srcs
+            .iter()
+            .filter(|x| x.dimensions.width >= width)
+            .min_by_key(|x| x.dimensions.width)
+            .or_else(|| self.largest())
+    }
+
+    /// the full size jpeg the srcsets were resized from
+    pub fn original(&self) -> Option<Url> {
+        self.largest()?.original()
+    }
+
+    /// check the photo can be laid out on every breakpoint of
+    /// [`ResponsivePhotoGrid::from_layout_data`]
+    pub fn validate(&self) -> Result<(), PhotoError> {
+        self.validate_on(&DEFAULT_BREAKPOINTS)
+    }
+
+    /// check the photo can be laid out on every one of `breakpoints` and
+    /// has an id to refer to it by
+    pub fn validate_on(&self, breakpoints: &[Breakpoint]) -> Result<(), PhotoError> {
+        breakpoints.iter().try_for_each(|breakpoint| {
+            precision_candidates(breakpoint.columns).try_for_each(|short_edge| {
+                try_layout_size(self, breakpoint.clamp, short_edge).map(|_| ())
+            })
+        })?;
+        self.id().map(|_| ()).ok_or(PhotoError::NoId)
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhotoGrid<T> {
@@ -26,19 +142,117 @@ pub struct PhotoGrid<T> {
     pub width: usize,
 }
 
+impl<T> PhotoGrid<T> {
+    /// number of rows spanned by the contents
+    pub fn height(&self) -> usize {
+        self.grid
+            .iter()
+            .map(|c| c.origin.y + c.size.height)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// the contents row by row from the top left, the order they are read in
+    pub fn reading_order(&self) -> impl Iterator<Item = &T> {
+        let mut contents: Vec<&GridContent<T>> = self.grid.iter().collect();
+        contents.sort_by_key(|c| (c.origin.y, c.origin.x));
+        contents.into_iter().map(|c| c.content())
+    }
+
+    /// the position in `grid` of the content occupying each cell, row by row
+    fn occupancy(&self) -> Vec<Option<usize>> {
+        let mut out = vec![None; self.width * self.height()];
+        self.grid.iter().enumerate().for_each(|(idx, c)| {
+            c.size.coords_iter().for_each(|Coord { x, y }| {
+                let cell = (c.origin.y + y) * self.width + c.origin.x + x;
+                if let Some(e) = out.get_mut(cell) {
+                    *e = Some(idx);
+                }
+            })
+        });
+        out
+    }
+}
+
+impl PhotoGrid<usize> {
+    /// apply `cb` to a grid holding the current placements, returning the
+    /// positions it leaves behind. fails with the ids of the photos which
+    /// no longer fit where they were placed, or with the error of `cb`
+    fn updated(
+        &self,
+        cb: impl FnOnce(&mut Grid<Option<usize>>) -> Result<(), GridError>,
+    ) -> Result<Self, PhotoError> {
+        let mut grid = Grid::new(self.width);
+        let ids: Vec<usize> = self
+            .grid
+            .iter()
+            .filter(|c| !grid.place(*c.content(), c.origin, &c.size))
+            .map(|c| *c.content())
+            .collect();
+        if !ids.is_empty() {
+            return Err(PhotoError::DoesNotFit {
+                ids,
+                columns: self.width,
+            });
+        }
+        cb(&mut grid)?;
+        Ok(PhotoGrid {
+            grid: grid.into_iter().collect(),
+            width: self.width,
+        })
+    }
+}
+
 impl<T> PhotoGrid<T>
 where
     T: std::fmt::Debug,
 {
-    pub fn new_with_mapper<U, C>(photos: &[T], width: usize, cb: C) -> Self
+    /// lay out the photos using the provided packing strategy
+    pub fn new_with_mapper<U, C, P>(photos: &[T], width: usize, strategy: &P, cb: C) -> Self
+    where
+        T: Copy,
+        C: for<'a> FnMut(&'a T) -> U,
+        U: Size,
+        P: PackingStrategy,
+    {
+        Self::new_with_pinned(photos, width, strategy, &[], cb).0
+    }
+
+    /// lay out the photos using the provided packing strategy, flowing them
+    /// around the `pinned` photos which keep their position and size. pinned
+    /// photos which overlap one before them or run off the side of the grid
+    /// are left out and returned
+    pub fn new_with_pinned<U, C, P>(
+        photos: &[T],
+        width: usize,
+        strategy: &P,
+        pinned: &[GridContent<T>],
+        cb: C,
+    ) -> (Self, Vec<GridContent<T>>)
     where
         T: Copy,
         C: for<'a> FnMut(&'a T) -> U,
         U: Size,
+        P: PackingStrategy,
     {
         let mut grid = Grid::new(width);
-        grid.add_all(photos.iter().map(cb));
-        let mut nullable_vec: Vec<Option<T>> = photos.iter().map(|x| Some(*x)).collect();
+        // pinned photos are identified after the packed ones
+        let rejected = grid
+            .pin(
+                pinned
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, c)| c.map(|_| photos.len() + idx)),
+            )
+            .into_iter()
+            .map(|c| pinned[c.content() - photos.len()])
+            .collect();
+        grid.add_all_with(strategy, photos.iter().map(cb));
+        let mut nullable_vec: Vec<Option<T>> = photos
+            .iter()
+            .chain(pinned.iter().map(|c| c.content()))
+            .map(|x| Some(*x))
+            .collect();
         let content = grid
             .into_iter()
             .map(|c| {
@@ -48,10 +262,11 @@ where
                 })
             })
             .collect::<Vec<_>>();
-        PhotoGrid {
+        let out = PhotoGrid {
             grid: content,
             width,
-        }
+        };
+        (out, rejected)
     }
 
     fn grow_non_intersecting(mut self) -> Self {
@@ -88,20 +303,30 @@ where
 pub struct ResponsivePhotoGrid<T> {
     grids: Vec<PhotoGrid<usize>>,
     data: Vec<T>,
+    /// the rounding used on each breakpoint, when it was chosen automatically
+    precisions: Vec<Precision>,
+    /// the viewports each grid is shown on, when built from a breakpoint table
+    breakpoints: Vec<Breakpoint>,
 }
 
 impl<T> ResponsivePhotoGrid<T> {
-    pub fn new<C, U>(photos: Vec<T>, sizes: impl IntoIterator<Item = usize>, mut cb: C) -> Self
+    pub fn new<C, U, P>(
+        photos: Vec<T>,
+        sizes: impl IntoIterator<Item = usize>,
+        strategy: &P,
+        mut cb: C,
+    ) -> Self
     where
         C: for<'a> FnMut(&'a T, (usize, usize)) -> U,
         U: Size,
+        P: PackingStrategy,
     {
         let ids: Vec<usize> = photos.iter().enumerate().map(|(idx, _)| idx).collect();
         let grids = sizes
             .into_iter()
             .enumerate()
             .map(|(idx, size)| {
-                PhotoGrid::new_with_mapper(ids.as_slice(), size, |id| {
+                PhotoGrid::new_with_mapper(ids.as_slice(), size, strategy, |id| {
                     cb(photos.get(*id).unwrap(), (idx, size))
                 })
             })
@@ -109,9 +334,35 @@ impl<T> ResponsivePhotoGrid<T> {
         Self {
             data: photos,
             grids,
+            precisions: Vec::new(),
+            breakpoints: Vec::new(),
         }
     }
 
+    /// the rounding precision chosen for each breakpoint and the aspect
+    /// error it introduces. empty unless the precision was chosen by the grid
+    pub fn precisions(&self) -> &[Precision] {
+        &self.precisions
+    }
+
+    /// the breakpoint each grid is laid out for, in the order of
+    /// [`ResponsivePhotoGrid::grids`]. empty unless the grid was built from
+    /// a breakpoint table
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// the photos in the order they were laid out
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// the layout of every breakpoint, holding the position of each photo in
+    /// [`ResponsivePhotoGrid::data`]
+    pub fn layouts(&self) -> &[PhotoGrid<usize>] {
+        &self.grids
+    }
+
     pub fn grids(&self) -> impl Iterator<Item = PhotoGrid<&T>> {
         self.grids.iter().map(|grid| {
             let width = grid.width;
@@ -125,18 +376,95 @@ impl<T> ResponsivePhotoGrid<T> {
         })
     }
 
-    pub fn contents_at(&self, n: usize) -> impl Iterator<Item = (&T, &GridContent<usize>)> {
-        self.grids.iter().filter_map(move |grid| {
-            grid.grid
-                .get(n)
-                .map(|idx| (self.data.get(*idx.content()).unwrap(), idx))
+    /// every photo of [`ResponsivePhotoGrid::data`] with its placement on each
+    /// breakpoint, none on the breakpoints it isn't placed on
+    pub fn contents(&self) -> impl Iterator<Item = (&T, Vec<Option<&GridContent<usize>>>)> {
+        let by_id: Vec<HashMap<usize, &GridContent<usize>>> = self
+            .grids
+            .iter()
+            .map(|grid| grid.grid.iter().map(|c| (*c.content(), c)).collect())
+            .collect();
+        self.data.iter().enumerate().map(move |(n, photo)| {
+            let placements = by_id.iter().map(|grid| grid.get(&n).copied()).collect();
+            (photo, placements)
         })
     }
 
-    /// returns the length of the first grid.
-    /// this assumes all inner grids have the same number of items
+    /// the number of photos
     pub fn contents_len(&self) -> usize {
-        self.grids.first().map(|g| g.grid.len()).unwrap_or_default()
+        self.data.len()
+    }
+
+    /// apply `cb` to every breakpoint. nothing changes unless every
+    /// breakpoint could be updated
+    fn update_all(
+        &mut self,
+        mut cb: impl FnMut(usize, &mut Grid<Option<usize>>) -> Result<(), GridError>,
+    ) -> Result<(), PhotoError> {
+        self.grids = self
+            .grids
+            .iter()
+            .enumerate()
+            .map(|(idx, g)| g.updated(|grid| cb(idx, grid)))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// remove the photo from every breakpoint and from
+    /// [`ResponsivePhotoGrid::data`]. the ids of the photos after it shift
+    /// down by one, as with [`Vec::remove`]
+    pub fn remove(&mut self, id: usize) -> Result<T, PhotoError> {
+        if id >= self.data.len() {
+            return Err(PhotoError::OutOfBounds {
+                id,
+                len: self.data.len(),
+            });
+        }
+        self.update_all(|_, grid| {
+            grid.remove(&id);
+            Ok(())
+        })?;
+        self.grids.iter_mut().for_each(|g| {
+            g.grid = std::mem::take(&mut g.grid)
+                .into_iter()
+                .map(|c| c.map(|n| if n > id { n - 1 } else { n }))
+                .collect();
+        });
+        Ok(self.data.remove(id))
+    }
+
+    /// add a photo at the first position it fits on every breakpoint without
+    /// moving the photos already placed. `cb` maps the photo to its size as in
+    /// [`ResponsivePhotoGrid::new`]. returns the id of the new photo. nothing
+    /// changes if its size is empty or wider than any of the grids
+    pub fn insert<C, U>(&mut self, photo: T, mut cb: C) -> Result<usize, PhotoError>
+    where
+        C: FnMut(&T, (usize, usize)) -> U,
+        U: Size,
+    {
+        let id = self.data.len();
+        let widths: Vec<usize> = self.grids.iter().map(|g| g.width).collect();
+        self.update_all(|idx, grid| grid.try_insert(id, &cb(&photo, (idx, widths[idx]))))?;
+        self.data.push(photo);
+        Ok(id)
+    }
+
+    /// slide photos up into the space freed by removals on every breakpoint
+    pub fn compact(&mut self) -> Result<(), PhotoError> {
+        self.update_all(|_, grid| {
+            grid.compact();
+            Ok(())
+        })
+    }
+
+    /// grow photos into the holes left by the packer on every breakpoint
+    pub fn fill_holes(mut self, config: FillConfig) -> Self {
+        self.grids = self
+            .grids
+            .into_iter()
+            .map(|g| g.fill_holes(config))
+            .collect();
+        self
     }
 
     /// on the smallest breakpoint we want to insert an outer container which always occupies the full width
@@ -150,36 +478,140 @@ impl<T> ResponsivePhotoGrid<T> {
     }
 }
 
+/// the short edges considered when rounding photos on a breakpoint. wider
+/// breakpoints can afford more cells per photo to match aspect ratios
+/// closer. a short edge of at most a third of the columns keeps room for
+/// two 3:2 landscape photos side by side
+fn precision_candidates(columns: usize) -> std::ops::RangeInclusive<usize> {
+    2..=(columns / 3).max(2)
+}
+
+/// the fewest photos of the mean rounded width a row must hold for a
+/// short edge above the smallest candidate to be considered
+const MIN_PHOTOS_PER_ROW: usize = 3;
+
+/// the candidate short edge with the smallest aspect error among those
+/// which keep the grid dense. larger short edges match aspect ratios closer
+/// but fit fewer photos per row, making the page taller and sparser
+fn best_precision(dimensions: &[Dimension], columns: usize) -> Option<Precision> {
+    let candidates = precision_candidates(columns);
+    let smallest = *candidates.start();
+    let dense = |short_edge: &usize| {
+        let Some(nonzero) = NonZeroUsize::new(*short_edge) else {
+            return false;
+        };
+        let total: usize = dimensions
+            .iter()
+            .map(|d| Rounded::new(d, nonzero).width())
+            .sum();
+        *short_edge == smallest || total * MIN_PHOTOS_PER_ROW <= columns * dimensions.len()
+    };
+    Precision::best(dimensions, candidates.filter(dense))
+}
+
 impl ResponsivePhotoGrid<PhotoLayoutData> {
-    pub fn from_layout_data(data: Vec<PhotoLayoutData>) -> Self {
-        ResponsivePhotoGrid::new(data, [3, 4, 6, 8, 12], |x, (idx, size)| {
-            let dimensions = x
-                .srcs
-                .iter()
-                .map(|x| x.dimensions)
-                .max_by_key(|dim| dim.width)
-                .expect("There must be at least 1 srcset");
-            let rounded = RoundedAspectRatio::<2>::from_size(&dimensions);
-            let clamp = match (idx, size) {
-                (0, x) => ClampConfig {
-                    min_width: Some(x),
-                    max_width: Some(rounded.width()),
-                },
-                (_, x) => ClampConfig {
-                    min_width: None,
-                    max_width: Some(x),
-                },
-            };
+    /// lay out the photos on each of the [`DEFAULT_BREAKPOINTS`]
+    ///
+    /// # Panics
+    ///
+    /// panics if any photo is malformed, see [`ResponsivePhotoGrid::try_from_layout_data`]
+    pub fn from_layout_data(data: Vec<PhotoLayoutData>, strategy: &impl PackingStrategy) -> Self {
+        Self::from_layout_data_on(data, &DEFAULT_BREAKPOINTS, strategy)
+    }
+
+    /// lay out the photos on each of `breakpoints`
+    ///
+    /// # Panics
+    ///
+    /// panics if any photo is malformed, see [`ResponsivePhotoGrid::try_from_layout_data_on`]
+    pub fn from_layout_data_on(
+        data: Vec<PhotoLayoutData>,
+        breakpoints: &[Breakpoint],
+        strategy: &impl PackingStrategy,
+    ) -> Self {
+        let dimensions: Vec<Dimension> = data
+            .iter()
+            .map(|x| x.dimensions().expect("There must be at least 1 srcset"))
+            .collect();
+        let precisions: Vec<Precision> = breakpoints
+            .iter()
+            .map(|breakpoint| {
+                best_precision(&dimensions, breakpoint.columns)
+                    .expect("There must be at least 1 candidate precision")
+            })
+            .collect();
+
+        let short_edges: Vec<usize> = precisions.iter().map(|p| p.short_edge).collect();
+        let columns = breakpoints.iter().map(|b| b.columns);
+        let mut out = ResponsivePhotoGrid::new(data, columns, strategy, |x, (idx, _)| {
+            layout_size(x, breakpoints[idx].clamp, short_edges[idx])
+        });
+        out.precisions = precisions;
+        out.breakpoints = breakpoints.to_vec();
+        out
+    }
+
+    pub fn try_from_layout_data(
+        data: Vec<PhotoLayoutData>,
+        strategy: &impl PackingStrategy,
+    ) -> Result<Self, PhotoError> {
+        Self::try_from_layout_data_on(data, &DEFAULT_BREAKPOINTS, strategy)
+    }
 
-            rounded.clamp_width_to(clamp)
+    pub fn try_from_layout_data_on(
+        data: Vec<PhotoLayoutData>,
+        breakpoints: &[Breakpoint],
+        strategy: &impl PackingStrategy,
+    ) -> Result<Self, PhotoError> {
+        data.iter()
+            .try_for_each(|photo| photo.validate_on(breakpoints))?;
+        Ok(Self::from_layout_data_on(data, breakpoints, strategy))
+    }
+
+    /// add a photo on every breakpoint, see [`ResponsivePhotoGrid::insert`]
+    pub fn insert_layout_data(&mut self, photo: PhotoLayoutData) -> Result<usize, PhotoError> {
+        let short_edges: Vec<usize> = self.precisions.iter().map(|p| p.short_edge).collect();
+        let clamps: Vec<ClampConfig> = self.breakpoints.iter().map(|b| b.clamp).collect();
+        self.insert(photo, |x, (idx, columns)| {
+            let short_edge = short_edges.get(idx).copied().unwrap_or(2);
+            let clamp = clamps.get(idx).copied().unwrap_or(ClampConfig {
+                min_width: None,
+                max_width: Some(columns),
+            });
+            layout_size(x, clamp, short_edge)
         })
     }
 }
 
+/// the size of a photo clamped to `clamp`, rounded so its short edge
+/// spans `short_edge` cells
+fn layout_size(x: &PhotoLayoutData, clamp: ClampConfig, short_edge: usize) -> Dimension {
+    try_layout_size(x, clamp, short_edge).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// the size of a photo on a grid, see [`layout_size`]. photos are scaled by
+/// their [`PhotoLayoutData::emphasis`], grids too narrow for the full factor
+/// use the largest one that fits
+fn try_layout_size(
+    x: &PhotoLayoutData,
+    clamp: ClampConfig,
+    short_edge: usize,
+) -> Result<Dimension, PhotoError> {
+    let dimensions = x.dimensions().ok_or(PhotoError::NoSrcSet)?;
+    let rounded = Rounded::try_new(&dimensions, short_edge)?;
+    Ok(rounded
+        .emphasize(x.emphasis())
+        .within(clamp.max_width)
+        .try_clamp_width_to(clamp)?)
+}
+
 #[cfg(test)]
 mod tests {
     use cool_asserts::assert_matches;
-    use grid::{Coord, NormalizedAspectRatio, Orientation};
+    use grid::{
+        pack::{FirstFit, Lookahead},
+        NormalizedAspectRatio, Orientation,
+    };
 
     use super::*;
 
@@ -200,7 +632,7 @@ mod tests {
             },
         ];
 
-        let g = PhotoGrid::new_with_mapper(data.as_slice(), 4, |x| *x);
+        let g = PhotoGrid::new_with_mapper(data.as_slice(), 4, &FirstFit, |x| *x);
 
         assert_matches!(
             g.grid,
@@ -254,7 +686,7 @@ mod tests {
             },
         ];
 
-        let g = PhotoGrid::new_with_mapper(data.as_slice(), 4, |x| *x);
+        let g = PhotoGrid::new_with_mapper(data.as_slice(), 4, &FirstFit, |x| *x);
 
         assert_matches!(
             g.grid,
@@ -294,4 +726,441 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn it_should_fill_gaps_with_lookahead() {
+        let data = vec![
+            Dimension {
+                width: 1,
+                height: 1,
+            },
+            Dimension {
+                width: 1,
+                height: 2,
+            },
+            Dimension {
+                width: 2,
+                height: 3,
+            },
+        ];
+
+        let g = PhotoGrid::new_with_mapper(data.as_slice(), 3, &Lookahead::default(), |x| *x);
+
+        assert_matches!(
+            g.grid,
+            [
+                GridContent {
+                    origin: Coord { x: 0, y: 0 },
+                    size: Dimension {
+                        height: 1,
+                        width: 1
+                    },
+                    ..
+                },
+                GridContent {
+                    origin: Coord { x: 1, y: 0 },
+                    size: Dimension {
+                        height: 3,
+                        width: 2
+                    },
+                    ..
+                },
+                GridContent {
+                    origin: Coord { x: 0, y: 1 },
+                    size: Dimension {
+                        height: 2,
+                        width: 1
+                    },
+                    ..
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn it_should_flow_around_pinned_photos() {
+        let data = [Dimension {
+            width: 1,
+            height: 1,
+        }; 3];
+        let hero = GridContent::new(
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+            Coord { x: 0, y: 0 },
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+        );
+
+        let overlapping = GridContent::new(data[0], Coord { x: 1, y: 1 }, data[0]);
+
+        let (g, rejected) =
+            PhotoGrid::new_with_pinned(&data, 3, &FirstFit, &[hero, overlapping], |x| *x);
+        assert_matches!(
+            rejected.as_slice(),
+            [GridContent {
+                origin: Coord { x: 1, y: 1 },
+                ..
+            }]
+        );
+
+        let origins: Vec<_> = g.grid.iter().map(|c| (c.origin, c.size.width)).collect();
+        assert_eq!(
+            origins,
+            [
+                (Coord { x: 0, y: 0 }, 2),
+                (Coord { x: 2, y: 0 }, 1),
+                (Coord { x: 2, y: 1 }, 1),
+                (Coord { x: 0, y: 2 }, 1),
+            ]
+        );
+    }
+
+    /// photos rated 5, 2 and unrated
+    fn rated() -> Vec<PhotoLayoutData> {
+        serde_json::from_str(include_str!("../fixtures/rated.json")).unwrap()
+    }
+
+    #[test]
+    fn it_should_read_ratings() {
+        let photos = rated();
+        let ratings: Vec<_> = photos.iter().map(PhotoLayoutData::rating).collect();
+        assert_eq!(ratings, [5, 2, 0]);
+        let emphasis: Vec<_> = photos.iter().map(PhotoLayoutData::emphasis).collect();
+        assert_eq!(emphasis, [2, 1, 1]);
+    }
+
+    #[test]
+    fn it_should_emphasize_highly_rated_photos() {
+        let mut photos = rated();
+        photos.truncate(2);
+
+        let g = ResponsivePhotoGrid::from_layout_data(photos, &FirstFit);
+        let sizes: Vec<Vec<_>> = g
+            .grids()
+            .map(|grid| {
+                let mut sizes: Vec<_> = grid
+                    .grid
+                    .iter()
+                    .map(|c| (c.content().emphasis(), c.size.width, c.size.height))
+                    .collect();
+                sizes.sort();
+                sizes
+            })
+            .collect();
+
+        assert_eq!(
+            sizes,
+            [
+                [(1, 3, 2), (2, 3, 2)],
+                [(1, 3, 2), (2, 3, 2)],
+                [(1, 3, 2), (2, 6, 4)],
+                [(1, 3, 2), (2, 6, 4)],
+                [(1, 3, 2), (2, 6, 4)],
+            ]
+        );
+        // grids too narrow to double the photo keep its 3:2 shape
+        assert!(sizes
+            .iter()
+            .flatten()
+            .all(|(_, width, height)| width * 2 == height * 3));
+    }
+
+    #[test]
+    fn it_should_fit_panoramas_on_the_narrowest_grid() {
+        let photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        // a 3.2:1 panorama rounds to 6x2, twice as wide as the mobile grid
+        let panorama = &photos[5];
+        let rounded = Rounded::try_new(&panorama.dimensions().unwrap(), 2).unwrap();
+        assert_eq!(rounded.width(), 6);
+
+        let mobile = DEFAULT_BREAKPOINTS[0];
+        assert_matches!(
+            layout_size(panorama, mobile.clamp, 2),
+            Dimension {
+                width: 3,
+                height: 1
+            }
+        );
+
+        let g = ResponsivePhotoGrid::from_layout_data(photos, &FirstFit);
+        let grid = g.grids().next().unwrap();
+        assert!(grid.grid.iter().all(|c| c.origin.x + c.size.width <= 3));
+    }
+
+    #[test]
+    fn it_should_report_precision_per_breakpoint() {
+        let photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        let g = ResponsivePhotoGrid::from_layout_data(photos.clone(), &FirstFit);
+        let short_edges: Vec<_> = g.precisions().iter().map(|p| p.short_edge).collect();
+        // larger short edges would match the mostly 3:2 photos closer on the
+        // widest grid but leave only two per row
+        assert_eq!(short_edges, [2, 2, 2, 2, 2]);
+        assert!(g.precisions().iter().all(|p| p.max_error < 0.07));
+
+        // 4:5 portraits get closer to their shape as the grid widens
+        let portraits: Vec<_> = photos
+            .into_iter()
+            .take(3)
+            .map(|mut photo| {
+                photo.srcs.iter_mut().for_each(|src| {
+                    src.dimensions = Dimension {
+                        width: 1600,
+                        height: 2000,
+                    }
+                });
+                photo
+            })
+            .collect();
+        let breakpoints = [
+            Breakpoint::new(0, Some(768), 6),
+            Breakpoint::new(768, Some(1536), 9),
+            Breakpoint::new(1536, None, 12),
+        ];
+        let g = ResponsivePhotoGrid::from_layout_data_on(portraits, &breakpoints, &FirstFit);
+        let short_edges: Vec<_> = g.precisions().iter().map(|p| p.short_edge).collect();
+        assert_eq!(short_edges, [2, 3, 4]);
+        let sizes: Vec<_> = g
+            .grids()
+            .map(|grid| (grid.grid[0].size.width, grid.grid[0].size.height))
+            .collect();
+        assert_eq!(sizes, [(2, 2), (3, 4), (4, 5)]);
+    }
+
+    #[test]
+    fn it_should_lay_out_custom_breakpoints() {
+        let mut photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        photos.truncate(4);
+        let breakpoints = [
+            Breakpoint::new(0, Some(900), 5),
+            Breakpoint::new(900, None, 16),
+        ];
+
+        let g = ResponsivePhotoGrid::from_layout_data_on(photos, &breakpoints, &FirstFit);
+        assert_eq!(g.breakpoints(), breakpoints);
+        let widths: Vec<_> = g.grids().map(|grid| grid.width).collect();
+        assert_eq!(widths, [5, 16]);
+        assert_eq!(g.precisions().len(), 2);
+    }
+
+    #[test]
+    fn it_should_pick_a_src_at_least_as_wide() {
+        let photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        let width = |w| photos[0].src_at_least(w).map(|src| src.dimensions.width);
+        assert_eq!(width(1000), Some(1024));
+        assert_eq!(width(1024), Some(1024));
+        assert_eq!(width(4000), Some(2048));
+    }
+
+    #[test]
+    fn it_should_find_the_original() {
+        let photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        assert_eq!(
+            photos[0].original().map(String::from).as_deref(),
+            Some("https://cdn.seanaye.ca/original/_AYE7507.jpg")
+        );
+    }
+
+    #[test]
+    fn it_should_reject_malformed_photos() {
+        let mut photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        photos.truncate(3);
+        assert!(photos.iter().all(|photo| photo.validate().is_ok()));
+
+        photos[1].srcs.clear();
+        assert_eq!(photos[1].validate(), Err(PhotoError::NoSrcSet));
+        photos[2]
+            .srcs
+            .iter_mut()
+            .for_each(|src| src.dimensions.height = 0);
+        assert_matches!(
+            photos[2].validate(),
+            Err(PhotoError::Grid(GridError::EmptySize { .. }))
+        );
+
+        assert_matches!(
+            ResponsivePhotoGrid::try_from_layout_data(photos, &FirstFit).err(),
+            Some(PhotoError::NoSrcSet)
+        );
+    }
+
+    fn responsive() -> ResponsivePhotoGrid<Dimension> {
+        let data = [(2, 1), (2, 1), (1, 2), (1, 2)]
+            .map(|(width, height)| Dimension { width, height })
+            .to_vec();
+        ResponsivePhotoGrid::new(data, [2, 4], &FirstFit, |x, _| *x)
+    }
+
+    #[test]
+    fn it_should_remove_from_every_breakpoint() {
+        let mut g = responsive();
+        g.remove(0).unwrap();
+
+        g.grids().for_each(|grid| {
+            assert_eq!(grid.grid.len(), 3);
+            assert!(grid.grid.iter().all(|c| c.origin != Coord { x: 0, y: 0 }));
+        });
+
+        g.compact().unwrap();
+        let heights: Vec<_> = g.grids().map(|grid| grid.height()).collect();
+        assert_eq!(heights, [3, 2]);
+    }
+
+    #[test]
+    fn it_should_remove_from_the_data() {
+        let mut g = responsive();
+        let removed = g.remove(1).unwrap();
+        assert_matches!(
+            removed,
+            Dimension {
+                width: 2,
+                height: 1
+            }
+        );
+        assert_eq!(g.contents_len(), 3);
+
+        // the ids after the removed photo shift down with the data
+        g.grids().for_each(|grid| {
+            let mut shapes: Vec<_> = grid
+                .grid
+                .iter()
+                .map(|c| (c.content().width, c.content().height))
+                .collect();
+            shapes.sort();
+            assert_eq!(shapes, [(1, 2), (1, 2), (2, 1)]);
+        });
+        assert!(g.layouts().iter().all(|grid| {
+            let mut ids: Vec<_> = grid.grid.iter().map(|c| *c.content()).collect();
+            ids.sort();
+            ids == [0, 1, 2]
+        }));
+
+        assert_matches!(g.remove(3), Err(PhotoError::OutOfBounds { id: 3, len: 3 }));
+        assert_eq!(g.contents_len(), 3);
+    }
+
+    #[test]
+    fn it_should_report_photos_which_no_longer_fit() {
+        let mut g = responsive();
+        g.grids[1].grid[0].size.width = 5;
+        let placements = |g: &ResponsivePhotoGrid<Dimension>| -> Vec<Vec<_>> {
+            g.layouts()
+                .iter()
+                .map(|grid| {
+                    grid.grid
+                        .iter()
+                        .map(|c| (*c.content(), c.origin, c.size.width, c.size.height))
+                        .collect()
+                })
+                .collect()
+        };
+        let before = placements(&g);
+
+        assert_eq!(
+            g.compact(),
+            Err(PhotoError::DoesNotFit {
+                ids: vec![0],
+                columns: 4
+            })
+        );
+        assert_matches!(
+            g.insert(
+                Dimension {
+                    width: 1,
+                    height: 1
+                },
+                |x, _| *x
+            ),
+            Err(_)
+        );
+        assert_eq!(placements(&g), before);
+        assert_eq!(g.contents_len(), 4);
+    }
+
+    #[test]
+    fn it_should_pair_contents_by_id() {
+        let mut g = responsive();
+        g.remove(0).unwrap();
+        // leave the last photo off the first breakpoint
+        let last = g.grids[0].grid.iter().position(|c| *c.content() == 2);
+        g.grids[0].grid.remove(last.unwrap());
+
+        let contents: Vec<_> = g.contents().collect();
+        assert_eq!(contents.len(), 3);
+        contents
+            .iter()
+            .enumerate()
+            .for_each(|(n, (photo, placed))| {
+                assert!(std::ptr::eq(*photo, &g.data()[n]));
+                assert_eq!(placed.len(), 2);
+                assert!(placed.iter().flatten().all(|c| *c.content() == n));
+            });
+        assert!(contents[2].1[0].is_none());
+        assert!(contents[2].1[1].is_some());
+    }
+
+    #[test]
+    fn it_should_read_row_by_row() {
+        let size = Dimension {
+            width: 1,
+            height: 1,
+        };
+        let g = PhotoGrid {
+            grid: [(2, 0), (0, 1), (1, 0), (0, 0)]
+                .into_iter()
+                .enumerate()
+                .map(|(id, (x, y))| GridContent::new(id, Coord { x, y }, size))
+                .collect(),
+            width: 3,
+        };
+
+        assert_eq!(g.reading_order().copied().collect::<Vec<_>>(), [3, 2, 0, 1]);
+    }
+
+    #[test]
+    fn it_should_insert_on_every_breakpoint() {
+        let mut g = responsive();
+        let id = g
+            .insert(
+                Dimension {
+                    width: 2,
+                    height: 2,
+                },
+                |x, _| *x,
+            )
+            .unwrap();
+
+        assert_eq!(id, 4);
+        g.grids().for_each(|grid| {
+            assert_eq!(grid.grid.len(), 5);
+            assert!(grid
+                .grid
+                .iter()
+                .any(|c| c.content().height == 2 && c.size.width == 2));
+        });
+
+        // 3 wide fits the 4 column grid but not the 2 column one
+        let wide = Dimension {
+            width: 3,
+            height: 1,
+        };
+        assert_matches!(
+            g.insert(wide, |x, _| *x),
+            Err(PhotoError::Grid(GridError::TooWide {
+                width: 3,
+                columns: 2
+            }))
+        );
+        assert_eq!(g.contents_len(), 5);
+        assert!(g.grids().all(|grid| grid.grid.len() == 5));
+    }
 }
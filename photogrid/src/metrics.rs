@@ -0,0 +1,188 @@
+use grid::{aspect_error, Dimension};
+use serde::Serialize;
+
+use crate::{PhotoGrid, PhotoLayoutData, ResponsivePhotoGrid};
+
+/// numbers describing how well a grid is packed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayoutMetrics {
+    /// number of columns in the grid
+    pub width: usize,
+    /// number of rows in the grid
+    pub height: usize,
+    /// occupied cells divided by the total cells
+    pub fill_ratio: f64,
+    /// number of cells in each connected run of empty cells, ordered
+    /// by the first cell of the hole row by row
+    pub holes: Vec<usize>,
+    /// the largest relative difference between the aspect ratio a
+    /// photo is rendered at and its real aspect ratio
+    pub max_distortion: f64,
+}
+
+impl LayoutMetrics {
+    /// total number of empty cells
+    pub fn empty_cells(&self) -> usize {
+        self.holes.iter().sum()
+    }
+}
+
+impl<T> PhotoGrid<T> {
+    /// measure the layout. `cb` returns the real dimensions of a photo
+    pub fn metrics(&self, mut cb: impl FnMut(&T) -> Dimension) -> LayoutMetrics {
+        let occupancy = self.occupancy();
+        let height = self.height();
+        let occupied = occupancy.iter().filter(|e| e.is_some()).count();
+
+        let max_distortion = self
+            .grid
+            .iter()
+            .map(|c| aspect_error(&c.size, &cb(c.content())))
+            .fold(0.0, f64::max);
+
+        LayoutMetrics {
+            width: self.width,
+            height,
+            fill_ratio: match occupancy.len() {
+                0 => 1.0,
+                total => occupied as f64 / total as f64,
+            },
+            holes: holes(&occupancy, self.width),
+            max_distortion,
+        }
+    }
+}
+
+/// sizes of the connected runs of empty cells
+fn holes(occupancy: &[Option<usize>], width: usize) -> Vec<usize> {
+    let mut seen = vec![false; occupancy.len()];
+    let mut out = Vec::new();
+
+    for start in 0..occupancy.len() {
+        if seen[start] || occupancy[start].is_some() {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut size = 0;
+        while let Some(idx) = stack.pop() {
+            size += 1;
+            let (x, y) = (idx % width, idx / width);
+            let neighbours = [
+                x.checked_sub(1).map(|x| (x, y)),
+                (x + 1 < width).then_some((x + 1, y)),
+                y.checked_sub(1).map(|y| (x, y)),
+                Some((x, y + 1)),
+            ];
+            neighbours
+                .into_iter()
+                .flatten()
+                .map(|(x, y)| y * width + x)
+                .for_each(|n| {
+                    if occupancy.get(n).is_some_and(|e| e.is_none()) && !seen[n] {
+                        seen[n] = true;
+                        stack.push(n);
+                    }
+                });
+        }
+        out.push(size);
+    }
+    out
+}
+
+impl<T> ResponsivePhotoGrid<T> {
+    /// measure the layout of every breakpoint. `cb` returns the real
+    /// dimensions of a photo
+    pub fn metrics(&self, mut cb: impl FnMut(&T) -> Dimension) -> Vec<LayoutMetrics> {
+        self.grids()
+            .map(|grid| grid.metrics(|photo| cb(photo)))
+            .collect()
+    }
+}
+
+impl ResponsivePhotoGrid<PhotoLayoutData> {
+    /// measure the layout of every breakpoint against the largest srcset
+    pub fn layout_metrics(&self) -> Vec<LayoutMetrics> {
+        self.metrics(|photo| photo.dimensions().expect("There must be at least 1 srcset"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FillConfig;
+    use grid::pack::{FirstFit, PackingStrategy};
+
+    fn photos() -> Vec<PhotoLayoutData> {
+        serde_json::from_str(include_str!("../../data.json")).unwrap()
+    }
+
+    fn layout(strategy: &impl PackingStrategy) -> Vec<LayoutMetrics> {
+        ResponsivePhotoGrid::from_layout_data(photos(), strategy).layout_metrics()
+    }
+
+    #[test]
+    fn it_should_measure_holes() {
+        let data = [
+            Dimension {
+                width: 2,
+                height: 2,
+            },
+            Dimension {
+                width: 1,
+                height: 1,
+            },
+        ];
+        let g = PhotoGrid::new_with_mapper(&data, 4, &FirstFit, |x| *x);
+        let metrics = g.metrics(|_| Dimension {
+            width: 1,
+            height: 1,
+        });
+
+        assert_eq!(
+            metrics,
+            LayoutMetrics {
+                width: 4,
+                height: 2,
+                fill_ratio: 5.0 / 8.0,
+                holes: vec![3],
+                max_distortion: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn first_fit_regression() {
+        let metrics = layout(&FirstFit);
+
+        let widths: Vec<_> = metrics.iter().map(|m| m.width).collect();
+        assert_eq!(widths, [3, 4, 6, 8, 12]);
+        let heights: Vec<_> = metrics.iter().map(|m| m.height).collect();
+        // the mobile grid was 1602 rows before panoramas were clamped to its width
+        assert_eq!(heights, [1598, 827, 508, 383, 257]);
+        // wider grids should hold proportionally more photos per row rather
+        // than rounding them larger, so no breakpoint past the 4 column one
+        // uses noticeably more cells than it
+        let cells = |m: &LayoutMetrics| (m.width * m.height) as f64;
+        metrics[2..]
+            .iter()
+            .for_each(|m| assert!(cells(m) <= cells(&metrics[1]) * 1.1, "{m:?}"));
+        let minimum_fill = [1.0, 0.88, 0.96, 0.96, 0.95];
+        metrics
+            .iter()
+            .zip(minimum_fill)
+            .for_each(|(m, min)| assert!(m.fill_ratio >= min, "{m:?}"));
+    }
+
+    #[test]
+    fn filling_holes_should_improve_fill_ratio() {
+        let grid = ResponsivePhotoGrid::from_layout_data(photos(), &FirstFit);
+        let before = grid.layout_metrics();
+        let after = grid.fill_holes(FillConfig::default()).layout_metrics();
+
+        before.iter().zip(&after).for_each(|(a, b)| {
+            assert_eq!(a.height, b.height);
+            assert!(b.fill_ratio >= a.fill_ratio, "{a:?} {b:?}");
+        });
+    }
+}
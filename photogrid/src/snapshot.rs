@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use grid::{Coord, Dimension, GridContent, GridError, Precision};
+use serde::{Deserialize, Serialize};
+
+use crate::{Breakpoint, PhotoError, PhotoGrid, PhotoId, PhotoLayoutData, ResponsivePhotoGrid};
+
+/// the snapshot version written by [`ResponsivePhotoGrid::snapshot`]. bump it
+/// whenever the meaning of a field changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// a precomputed layout of every breakpoint, restored with
+/// [`ResponsivePhotoGrid::from_snapshot`] without running the packer again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutSnapshot {
+    pub version: u32,
+    pub layouts: Vec<BreakpointLayout>,
+}
+
+/// the layout of a single breakpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakpointLayout {
+    pub columns: usize,
+    /// the viewports the layout is shown on, when built from a breakpoint table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breakpoint: Option<Breakpoint>,
+    /// the rounding the layout was made with, when it was chosen automatically
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<Precision>,
+    /// the placed photos in visiting order
+    pub items: Vec<SnapshotItem>,
+}
+
+/// the photo `id` placed at column `x` and row `y`, spanning `width` by `height` cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotItem {
+    pub id: PhotoId,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl ResponsivePhotoGrid<PhotoLayoutData> {
+    /// the layout of every breakpoint, identifying photos by their [`PhotoId`]
+    pub fn snapshot(&self) -> Result<LayoutSnapshot, PhotoError> {
+        let ids = self
+            .data
+            .iter()
+            .map(|photo| photo.id().ok_or(PhotoError::NoId))
+            .collect::<Result<Vec<_>, _>>()?;
+        let layouts = self
+            .grids
+            .iter()
+            .enumerate()
+            .map(|(idx, grid)| BreakpointLayout {
+                columns: grid.width,
+                breakpoint: self.breakpoints.get(idx).copied(),
+                precision: self.precisions.get(idx).copied(),
+                items: grid
+                    .grid
+                    .iter()
+                    .map(|c| SnapshotItem {
+                        id: ids[*c.content()],
+                        x: c.origin.x,
+                        y: c.origin.y,
+                        width: c.size.width,
+                        height: c.size.height,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(LayoutSnapshot {
+            version: SNAPSHOT_VERSION,
+            layouts,
+        })
+    }
+
+    /// restore a layout made by [`ResponsivePhotoGrid::snapshot`]. photos are
+    /// matched by id so `data` may be in any order, photos the snapshot
+    /// doesn't place are left off every breakpoint
+    pub fn from_snapshot(
+        snapshot: LayoutSnapshot,
+        data: Vec<PhotoLayoutData>,
+    ) -> Result<Self, PhotoError> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(PhotoError::UnsupportedSnapshot {
+                found: snapshot.version,
+            });
+        }
+        let positions: HashMap<PhotoId, usize> = data
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, photo)| Some((photo.id()?, idx)))
+            .collect();
+
+        let grids = snapshot
+            .layouts
+            .iter()
+            .map(|layout| {
+                let grid = layout
+                    .items
+                    .iter()
+                    .map(|item| {
+                        let position = *positions
+                            .get(&item.id)
+                            .ok_or(PhotoError::UnknownPhoto { id: item.id })?;
+                        if item.x + item.width > layout.columns {
+                            return Err(GridError::TooWide {
+                                width: item.x + item.width,
+                                columns: layout.columns,
+                            }
+                            .into());
+                        }
+                        let origin = Coord {
+                            x: item.x,
+                            y: item.y,
+                        };
+                        let size = Dimension {
+                            width: item.width,
+                            height: item.height,
+                        };
+                        Ok(GridContent::new(position, origin, size))
+                    })
+                    .collect::<Result<_, PhotoError>>()?;
+                Ok(PhotoGrid {
+                    grid,
+                    width: layout.columns,
+                })
+            })
+            .collect::<Result<_, PhotoError>>()?;
+
+        // kept only when every layout has one, as on a freshly built grid
+        let breakpoints: Option<Vec<Breakpoint>> =
+            snapshot.layouts.iter().map(|l| l.breakpoint).collect();
+        let precisions: Option<Vec<Precision>> =
+            snapshot.layouts.iter().map(|l| l.precision).collect();
+        Ok(Self {
+            grids,
+            data,
+            precisions: precisions.unwrap_or_default(),
+            breakpoints: breakpoints.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cool_asserts::assert_matches;
+    use grid::pack::FirstFit;
+
+    use super::*;
+    use crate::PhotoLayoutData;
+
+    fn photos() -> Vec<PhotoLayoutData> {
+        let mut photos: Vec<PhotoLayoutData> =
+            serde_json::from_str(include_str!("../../data.json")).unwrap();
+        photos.truncate(12);
+        photos
+    }
+
+    #[test]
+    fn it_should_round_trip_through_json() {
+        let g = ResponsivePhotoGrid::from_layout_data(photos(), &FirstFit);
+        let snapshot = g.snapshot().unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let parsed: LayoutSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, snapshot);
+
+        let restored = ResponsivePhotoGrid::from_snapshot(parsed, photos()).unwrap();
+        assert_eq!(restored.snapshot(), Ok(snapshot));
+        assert_eq!(restored.breakpoints(), g.breakpoints());
+        assert_eq!(restored.precisions(), g.precisions());
+    }
+
+    #[test]
+    fn it_should_identify_photos_by_id() {
+        let g = ResponsivePhotoGrid::from_layout_data(photos(), &FirstFit);
+        let snapshot = g.snapshot().unwrap();
+        let first = photos()[0].id().unwrap();
+        assert!(snapshot
+            .layouts
+            .iter()
+            .all(|layout| layout.items.iter().any(|item| item.id == first)));
+
+        // the same photos in another order restore the same placements
+        let mut reordered = photos();
+        reordered.reverse();
+        let restored = ResponsivePhotoGrid::from_snapshot(snapshot.clone(), reordered).unwrap();
+        assert_eq!(restored.data()[0].id(), photos()[11].id());
+        assert_eq!(restored.snapshot(), Ok(snapshot));
+    }
+
+    #[test]
+    fn it_should_reject_mismatched_snapshots() {
+        let g = ResponsivePhotoGrid::from_layout_data(photos(), &FirstFit);
+        let snapshot = g.snapshot().unwrap();
+
+        let mut newer = snapshot.clone();
+        newer.version += 1;
+        assert_matches!(
+            ResponsivePhotoGrid::from_snapshot(newer, photos()).err(),
+            Some(PhotoError::UnsupportedSnapshot { found: 2 })
+        );
+
+        let mut fewer = photos();
+        let missing = fewer.remove(4).id().unwrap();
+        assert_eq!(
+            ResponsivePhotoGrid::from_snapshot(snapshot.clone(), fewer).err(),
+            Some(PhotoError::UnknownPhoto { id: missing })
+        );
+
+        let mut narrow = snapshot;
+        narrow.layouts[0].columns = 2;
+        assert_matches!(
+            ResponsivePhotoGrid::from_snapshot(narrow, photos()).err(),
+            Some(PhotoError::Grid(GridError::TooWide { columns: 2, .. }))
+        );
+    }
+
+    #[test]
+    fn it_should_need_an_id_for_every_photo() {
+        let mut photos = photos();
+        photos[3].srcs.iter_mut().for_each(|src| {
+            src.url.set_path("/");
+        });
+        photos[3].metadata.remove(crate::CHECKSUM_KEY);
+        assert_eq!(photos[3].validate(), Err(PhotoError::NoId));
+
+        let g = ResponsivePhotoGrid::from_layout_data(photos, &FirstFit);
+        assert_eq!(g.snapshot(), Err(PhotoError::NoId));
+    }
+}
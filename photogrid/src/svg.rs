@@ -0,0 +1,72 @@
+use crate::PhotoGrid;
+
+impl<T> PhotoGrid<T> {
+    /// render each content as a labelled rectangle for debugging layouts.
+    /// the label is the position of the content in `grid` followed by its size
+    pub fn to_svg(&self, cell_size: usize) -> String {
+        let width = self.width * cell_size;
+        let height = self.height() * cell_size;
+
+        let rects: String = self
+            .grid
+            .iter()
+            .enumerate()
+            .map(|(idx, c)| {
+                let x = c.origin.x * cell_size;
+                let y = c.origin.y * cell_size;
+                let w = c.size.width * cell_size;
+                let h = c.size.height * cell_size;
+                let rect = format!(
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" fill=\"#ddd\" stroke=\"#333\"/>"
+                );
+                let label = format!(
+                    "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{idx} {}x{}</text>",
+                    x + w / 2,
+                    y + h / 2,
+                    c.size.width,
+                    c.size.height,
+                );
+                format!("{rect}\n{label}\n")
+            })
+            .collect();
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"{font}\">\n{rects}</svg>\n",
+            font = (cell_size / 4).max(1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grid::{pack::FirstFit, Dimension};
+
+    use crate::PhotoGrid;
+
+    #[test]
+    fn it_should_render_svg() {
+        let data = [
+            Dimension {
+                width: 2,
+                height: 1,
+            },
+            Dimension {
+                width: 1,
+                height: 2,
+            },
+        ];
+        let g = PhotoGrid::new_with_mapper(&data, 3, &FirstFit, |x| *x);
+
+        assert_eq!(
+            g.to_svg(40),
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120\" height=\"80\" viewBox=\"0 0 120 80\" font-family=\"monospace\" font-size=\"10\">\n",
+                "<rect x=\"0\" y=\"0\" width=\"80\" height=\"40\" fill=\"#ddd\" stroke=\"#333\"/>\n",
+                "<text x=\"40\" y=\"20\" text-anchor=\"middle\" dominant-baseline=\"middle\">0 2x1</text>\n",
+                "<rect x=\"80\" y=\"0\" width=\"40\" height=\"80\" fill=\"#ddd\" stroke=\"#333\"/>\n",
+                "<text x=\"100\" y=\"40\" text-anchor=\"middle\" dominant-baseline=\"middle\">1 1x2</text>\n",
+                "</svg>\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use std::{env, fs, path::PathBuf};
+
+use grid::pack::FirstFit;
+use photogrid::{FillConfig, PhotoLayoutData, ResponsivePhotoGrid};
+
+/// lay out every valid photo in data.json the way `app::layout_photos` does
+/// and write the snapshot served at /layout.json, so it can be diffed between
+/// deploys without packing anything when the server starts
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let data = manifest_dir.join("../data.json");
+    println!("cargo:rerun-if-changed={}", data.display());
+
+    let content = fs::read_to_string(&data)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", data.display()));
+    let photos: Vec<PhotoLayoutData> = serde_json::from_str(&content)
+        .unwrap_or_else(|err| panic!("invalid photo data in {}: {err}", data.display()));
+    let photos = photos
+        .into_iter()
+        .filter(|photo| photo.validate().is_ok())
+        .collect();
+
+    let snapshot = ResponsivePhotoGrid::from_layout_data(photos, &FirstFit)
+        .fill_holes(FillConfig::default())
+        .snapshot()
+        .expect("validated photos have ids");
+
+    let out_file = PathBuf::from(env::var("OUT_DIR").unwrap()).join("layout.json");
+    fs::write(out_file, serde_json::to_string(&snapshot).unwrap()).unwrap();
+}
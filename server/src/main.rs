@@ -1,22 +1,29 @@
 #![allow(dead_code, unused_imports)]
 
 use app::*;
-use axum::Router;
+use axum::{extract::Query, http::header, response::IntoResponse, routing::get, Router};
 use bucket::{get_bucket, BucketAccess};
 use grid::{FromSize, RoundedAspectRatio, Size};
 use leptos::prelude::*;
 use leptos_axum::{generate_route_list, LeptosRoutes};
 use photo_search::PhotoAccess;
-use photogrid::{PhotoLayoutData, ResponsivePhotoGrid, SrcSet};
+use photogrid::{parse_ids, PhotoIndex, PhotoLayoutData, ResponsivePhotoGrid, SrcSet};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fs::File,
     io::{Read, Write},
+    num::NonZeroUsize,
     sync::Arc,
 };
 mod bucket;
 
+/// the number of photo layouts kept in memory between requests
+const LAYOUT_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(64) {
+    Some(size) => size,
+    None => unreachable!(),
+};
+
 async fn photo_data() -> anyhow::Result<impl Iterator<Item = PhotoLayoutData>> {
     let bucket = BucketAccess::new(get_bucket()?, "cdn.seanaye.ca");
 
@@ -55,7 +62,34 @@ where
     serde_json::from_str(s).unwrap()
 }
 
+/// the originals of the comma separated photo `ids`, one url per line.
+/// `index` holds the position of each id in `photos`
+fn export_originals(
+    photos: &[PhotoLayoutData],
+    index: &PhotoIndex,
+    ids: &str,
+) -> impl IntoResponse {
+    let body: String = parse_ids(ids)
+        .into_iter()
+        .filter_map(|id| photos.get(index.get(id)?))
+        .filter_map(|photo| photo.original())
+        .map(|url| format!("{url}\n"))
+        .collect();
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"photos.txt\"",
+            ),
+        ],
+        body,
+    )
+}
+
 #[tokio::main]
+// the listening address is logged by reference, newer clippy flags the borrow
+#[allow(clippy::useless_borrows_in_formatting)]
 async fn main() {
     simple_logger::init_with_level(log::Level::Debug).expect("couldn't initialize logging");
     #[cfg(debug_assertions)]
@@ -83,16 +117,54 @@ async fn main() {
     //     item.get_timestamp()
     // });
     // write_to_file(&g);
-    let data: Vec<PhotoLayoutData> = cached();
+    let data: Vec<PhotoLayoutData> = cached::<Vec<PhotoLayoutData>>()
+        .into_iter()
+        .filter(|photo| match photo.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                let url = photo.srcs.first().map(|src| src.url.as_str());
+                log::warn!("skipping malformed photo {url:?}: {e}");
+                false
+            }
+        })
+        .collect();
 
     let photos: Arc<[PhotoLayoutData]> = Arc::from(data);
+    // built once so permalinks, shared selections and exports look ids up
+    // without scanning every photo
+    let index = Arc::new(PhotoIndex::new(&photos));
+    let layouts = Arc::new(PhotoLayoutCache::new(LAYOUT_CACHE_SIZE));
 
     // build our application with a route
     let app = Router::new()
+        .route(
+            "/layout.json",
+            get(|| async {
+                (
+                    [(header::CONTENT_TYPE, "application/json")],
+                    include_str!(concat!(env!("OUT_DIR"), "/layout.json")),
+                )
+            }),
+        )
+        .route(
+            "/export",
+            get({
+                let photos = photos.clone();
+                let index = index.clone();
+                move |Query(query): Query<HashMap<String, String>>| async move {
+                    let ids = query.get("ids").map(String::as_str).unwrap_or_default();
+                    export_originals(&photos, &index, ids)
+                }
+            }),
+        )
         .leptos_routes_with_context(
             &leptos_options,
             routes,
-            move || provide_context(photos.clone()),
+            move || {
+                provide_context(photos.clone());
+                provide_context(index.clone());
+                provide_context(layouts.clone());
+            },
             {
                 let leptos_options = leptos_options.clone();
                 move || shell(leptos_options.clone())